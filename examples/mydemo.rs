@@ -1,7 +1,11 @@
 use std::{collections::HashMap, time::Duration};
 
 use log::debug;
-use pocketbase_sdk_rust::{client::PocketBase, error::Result, user::UserTypes};
+use pocketbase_sdk_rust::{
+    client::{PocketBase, RecordEvent},
+    error::Result,
+    user::UserTypes,
+};
 
 /// cargo run --example mydemo
 #[tokio::main]
@@ -35,15 +39,25 @@ async fn main() -> Result<()> {
 
     pb.refresh_token().await?;
 
-    pb.subscribe("users", "*", |event| async move {
-        debug!("users Got event {:#?}", event);
-    })
-    .await?;
+    pb.on_realtime_error(|topic, error| async move {
+        debug!("{topic} event error {error}");
+    });
+
+    let users = pb
+        .subscribe(
+            "users",
+            "*",
+            |event: RecordEvent<serde_json::Value>| async move {
+                debug!("users Got event {:#?}", event);
+            },
+        )
+        .await?;
 
     sleep_with_log(Duration::from_secs(60 * 5)).await;
     debug!("After 5m");
 
     sleep_with_log(Duration::from_secs(60 * 5)).await;
+    users.unsubscribe().await;
     debug!("Bye!!");
     Ok(())
 }
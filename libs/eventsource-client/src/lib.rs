@@ -2,6 +2,7 @@ mod client;
 mod config;
 mod error;
 mod event_parser;
+mod proxy;
 mod retry;
 
 pub use client::*;
@@ -9,3 +10,5 @@ pub use config::*;
 pub use error::*;
 pub use event_parser::Event;
 pub use event_parser::SSE;
+pub use proxy::ProxyConnector;
+pub use retry::{BackoffRetry, RetryStrategy};
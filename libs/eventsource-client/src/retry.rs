@@ -2,7 +2,7 @@ use std::time::{Duration, Instant};
 
 use rand::{thread_rng, Rng};
 
-pub(crate) trait RetryStrategy {
+pub trait RetryStrategy {
     /// Return the next amount of time a failed request should delay before re-attempting.
     fn next_delay(&mut self, current_time: Instant) -> Duration;
 
@@ -15,7 +15,9 @@ pub(crate) trait RetryStrategy {
 
 const DEFAULT_RESET_RETRY_INTERVAL: Duration = Duration::from_secs(60);
 
-pub(crate) struct BackoffRetry {
+/// Exponential backoff, optionally with jitter, that resets after a stretch
+/// of success.
+pub struct BackoffRetry {
     base_delay: Duration,
     max_delay: Duration,
     backoff_factor: u32,
@@ -0,0 +1,110 @@
+use serde::Serialize;
+
+use crate::{
+    client::PocketBase,
+    error::{Error, PocketBaseErrorResponse, Result},
+    records::operations::{
+        list::{ListQuery, PaginatedRecordList},
+        GeneralPocketBaseResponse,
+    },
+    user::AdminRecord,
+};
+
+impl PocketBase {
+    pub async fn list_admins<S: AsRef<str>>(
+        &self,
+        page: Option<u32>,
+        per_page: Option<u32>,
+        sort: Option<S>,
+        filter: Option<S>,
+    ) -> Result<PaginatedRecordList<AdminRecord>> {
+        let admins_path = self.resolve_server_generation().await?.admins_path();
+        let query = ListQuery {
+            page,
+            per_page,
+            sort: sort.map(|inner| inner.as_ref().to_string()),
+            filter: filter.map(|inner| inner.as_ref().to_string()),
+            expand: None,
+        };
+        match self
+            .send_get(admins_path, Some(&query.to_params()))
+            .await?
+            .json::<GeneralPocketBaseResponse<PaginatedRecordList<AdminRecord>>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    pub async fn view_admin<S: AsRef<str>>(&self, id: S) -> Result<AdminRecord> {
+        let admins_path = self.resolve_server_generation().await?.admins_path();
+        match self
+            .send_get(format!("{admins_path}/{}", id.as_ref()), None::<&()>)
+            .await?
+            .json::<GeneralPocketBaseResponse<AdminRecord>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    /// `model` carries `email`, `password`, `passwordConfirm` and optionally `avatar`.
+    pub async fn create_admin<T: Serialize>(&self, model: &T) -> Result<AdminRecord> {
+        let admins_path = self.resolve_server_generation().await?.admins_path();
+        match self
+            .send_post(admins_path, model)
+            .await?
+            .json::<GeneralPocketBaseResponse<AdminRecord>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    pub async fn update_admin<S: AsRef<str>, T: Serialize>(
+        &self,
+        id: S,
+        model: &T,
+    ) -> Result<AdminRecord> {
+        let admins_path = self.resolve_server_generation().await?.admins_path();
+        match self
+            .send_patch(format!("{admins_path}/{}", id.as_ref()), model)
+            .await?
+            .json::<GeneralPocketBaseResponse<AdminRecord>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    pub async fn delete_admin<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        let admins_path = self.resolve_server_generation().await?.admins_path();
+        let response = self
+            .send_delete(format!("{admins_path}/{}", id.as_ref()))
+            .await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        match response.json::<PocketBaseErrorResponse>().await {
+            Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+}
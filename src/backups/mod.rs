@@ -0,0 +1,110 @@
+use reqwest::{
+    multipart::{Form, Part},
+    Body, Response,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::io::AsyncRead;
+use tokio_util::io::ReaderStream;
+use url::Url;
+
+use crate::{
+    client::PocketBase,
+    error::{Error, PocketBaseErrorResponse, Result},
+    records::operations::GeneralPocketBaseResponse,
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFileInfo {
+    pub key: String,
+    /// Bytes.
+    pub size: u64,
+    pub modified: String,
+}
+
+static API_BACKUPS: &str = "/api/backups";
+
+impl PocketBase {
+    pub async fn list_backups(&self) -> Result<Vec<BackupFileInfo>> {
+        match self
+            .send_get(API_BACKUPS, None::<&()>)
+            .await?
+            .json::<GeneralPocketBaseResponse<Vec<BackupFileInfo>>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    /// An empty `name` lets the server generate one.
+    pub async fn create_backup<S: AsRef<str>>(&self, name: S) -> Result<()> {
+        let response = self
+            .send_post(API_BACKUPS, &json!({ "name": name.as_ref() }))
+            .await?;
+        Self::resolve_backup_response(response).await
+    }
+
+    /// Upload a backup zip, streaming it from `reader` so the archive is
+    /// never held in memory as a whole.
+    pub async fn upload_backup<S, R>(&self, file_name: S, reader: R) -> Result<()>
+    where
+        S: Into<String>,
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        let part = Part::stream(Body::wrap_stream(ReaderStream::new(reader)))
+            .file_name(file_name.into())
+            .mime_str("application/zip")
+            .map_err(|e| Error::InvalidParameter(Box::new(e)))?;
+        let response = self
+            .send_multipart(
+                format!("{API_BACKUPS}/upload"),
+                Form::new().part("file", part),
+            )
+            .await?;
+        Self::resolve_backup_response(response).await
+    }
+
+    pub async fn delete_backup<S: AsRef<str>>(&self, key: S) -> Result<()> {
+        let response = self
+            .send_delete(format!("{API_BACKUPS}/{}", key.as_ref()))
+            .await?;
+        Self::resolve_backup_response(response).await
+    }
+
+    /// The server restarts itself once the restore is done.
+    pub async fn restore_backup<S: AsRef<str>>(&self, key: S) -> Result<()> {
+        let response = self
+            .send_post(
+                format!("{API_BACKUPS}/{}/restore", key.as_ref()),
+                &json!({}),
+            )
+            .await?;
+        Self::resolve_backup_response(response).await
+    }
+
+    /// Download url for a backup, `token` comes from [`PocketBase::file_token`].
+    pub fn backup_download_url<S: AsRef<str>>(&self, token: S, key: S) -> Result<Url> {
+        let mut url = self
+            .base_url
+            .join(&format!("{API_BACKUPS}/{}", key.as_ref()))
+            .map_err(|e| Error::InvalidParameter(Box::new(e)))?;
+        url.query_pairs_mut().append_pair("token", token.as_ref());
+        Ok(url)
+    }
+
+    async fn resolve_backup_response(response: Response) -> Result<()> {
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        match response.json::<PocketBaseErrorResponse>().await {
+            Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+}
@@ -50,9 +50,8 @@ impl PocketBase {
         credentials.insert("identity".to_string(), email.into());
         credentials.insert("password".to_string(), password.into());
 
-        let response = self
-            .send_post("/api/admins/auth-with-password", &credentials)
-            .await?;
+        let path = self.resolve_server_generation().await?.admin_auth_path();
+        let response = self.send_post(path, &credentials).await?;
 
         self.resolve_authorization_response(response, UserTypes::Admin)
             .await?;
@@ -84,6 +83,7 @@ impl PocketBase {
         };
 
         debug!("Authentication success with user: {:#?}", self.user);
+        self.sync_realtime_auth();
 
         Ok(())
     }
@@ -92,18 +92,26 @@ impl PocketBase {
         if self.user.is_none() {
             return Err(Error::NotAuthenticated);
         }
+        let usertype = self.user.to_owned().unwrap().usertype;
+        let path = match usertype {
+            UserTypes::User => "/api/collections/users/auth-refresh",
+            UserTypes::Admin => self.resolve_server_generation().await?.admin_refresh_path(),
+        };
         let response = self
-            .send_post(
-                "/api/collections/users/auth-refresh",
-                &HashMap::<String, String>::default(),
-            )
+            .send_post(path, &HashMap::<String, String>::default())
             .await?;
-        self.resolve_authorization_response(response, self.user.to_owned().unwrap().usertype)
+        self.resolve_authorization_response(response, usertype)
             .await?;
 
         Ok(())
     }
 
+    /// Forget the logged in user. Realtime subscriptions carry on anonymously.
+    pub fn logout(&mut self) {
+        self.user = None;
+        self.sync_realtime_auth();
+    }
+
     pub fn is_auth_store_valid(&self) -> bool {
         if let Some(user) = &self.user {
             return user.is_valid();
@@ -1,44 +1,121 @@
+use std::sync::Arc;
+
+use futures::{future::BoxFuture, Future};
 use log::debug;
 use reqwest::{
     header::{self, AUTHORIZATION},
-    Response,
+    multipart::Form,
+    Client, Method, Request, RequestBuilder, Response,
 };
 use serde::Serialize;
 
-use super::PocketBase;
+use super::{PocketBase, RateLimiter, RetryPolicy};
 use crate::{
     error::{Error, Result},
     user::UserTypes,
 };
 
+/// Runs on every request after auth was applied, right before it goes out.
+pub type BeforeSendHook = Arc<dyn Fn(Request) -> Result<Request> + Send + Sync>;
+/// Runs on every response before it is handed back to the caller.
+pub type AfterSendHook =
+    Arc<dyn Fn(Response) -> BoxFuture<'static, Result<Response>> + Send + Sync>;
+
+/// Everything a built request goes through: `before_send` hooks, the rate
+/// limiter, retries and `after_send` hooks. Shared with the realtime task, a
+/// clone is a cheap snapshot to send with.
+#[derive(Default, Clone)]
+pub(crate) struct SendPipeline {
+    pub before_send: Vec<BeforeSendHook>,
+    pub after_send: Vec<AfterSendHook>,
+    pub retry_policy: Option<RetryPolicy>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl SendPipeline {
+    pub(crate) async fn execute(&self, client: &Client, mut request: Request) -> Result<Response> {
+        for hook in &self.before_send {
+            request = hook(request)?;
+        }
+
+        debug!("{} {}", request.method(), request.url());
+        let mut response = match self.execute_with_retry(client, request).await {
+            Ok(response) => response,
+            Err(e) => return Err(Error::RequestFailed(Box::new(e))),
+        };
+        for hook in &self.after_send {
+            response = hook(response).await?;
+        }
+        Ok(response)
+    }
+}
+
 impl PocketBase {
+    /// Register a hook that may inspect or rewrite each outgoing request
+    /// (headers, url, body), the realtime subscription requests included.
+    /// Hooks run in registration order.
+    pub fn before_send<F>(&mut self, hook: F)
+    where
+        F: Fn(Request) -> Result<Request> + Send + Sync + 'static,
+    {
+        self.pipeline
+            .lock()
+            .unwrap()
+            .before_send
+            .push(Arc::new(hook));
+    }
+
+    /// Register a hook that may inspect or replace each response.
+    /// Hooks run in registration order.
+    pub fn after_send<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn(Response) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response>> + Send + 'static,
+    {
+        self.pipeline
+            .lock()
+            .unwrap()
+            .after_send
+            .push(Arc::new(move |response| Box::pin(hook(response))));
+    }
+
+    pub(crate) fn request<S: AsRef<str>>(&self, method: Method, path: S) -> Result<RequestBuilder> {
+        match self.base_url.join(path.as_ref()) {
+            Ok(endpoint) => Ok(self
+                .client
+                .request(method, endpoint)
+                .headers(self.http.headers.clone())),
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    /// The request pipeline: auth header, `before_send` hooks, send (with
+    /// retries), `after_send` hooks.
+    pub(crate) async fn execute(&self, req: RequestBuilder) -> Result<Response> {
+        let authed_req = match &self.user {
+            Some(user) => match &user.usertype {
+                UserTypes::User => req.header(AUTHORIZATION, user.token.to_string()),
+                UserTypes::Admin => req.header(AUTHORIZATION, user.token.to_string()),
+            },
+            None => req,
+        };
+        let request = authed_req
+            .build()
+            .map_err(|e| Error::RequestFailed(Box::new(e)))?;
+        let pipeline = self.pipeline.lock().unwrap().clone();
+        pipeline.execute(&self.client, request).await
+    }
+
     pub async fn send_get<S: AsRef<str>, T: Serialize + ?Sized>(
         &self,
         path: S,
         query: Option<&T>,
     ) -> Result<Response> {
-        match self.base_url.join(path.as_ref()) {
-            Ok(endpoint) => {
-                let mut req = self.client.get(endpoint);
-                req = if let Some(inner_quey) = query {
-                    req.query(inner_quey)
-                } else {
-                    req
-                };
-                let authed_req = match &self.user {
-                    Some(user) => match &user.usertype {
-                        UserTypes::User => req.header(AUTHORIZATION, user.token.to_string()),
-                        UserTypes::Admin => req.header(AUTHORIZATION, user.token.to_string()),
-                    },
-                    None => req,
-                };
-                match authed_req.send().await {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(Error::RequestFailed(Box::new(e))),
-                }
-            }
-            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        let mut req = self.request(Method::GET, path)?;
+        if let Some(inner_query) = query {
+            req = req.query(inner_query);
         }
+        self.execute(req).await
     }
 
     pub async fn send_post<S: AsRef<str>, T: Serialize + Sized>(
@@ -46,29 +123,8 @@ impl PocketBase {
         path: S,
         body: &T,
     ) -> Result<Response> {
-        match self.base_url.join(path.as_ref()) {
-            Ok(endpoint) => {
-                let body = serde_json::to_string(body).unwrap_or(String::default());
-                debug!("{} - body={body}", endpoint.as_ref());
-                let req = self
-                    .client
-                    .post(endpoint)
-                    .header(header::CONTENT_TYPE, "application/json")
-                    .body(body);
-                let authed_req = match &self.user {
-                    Some(user) => match &user.usertype {
-                        UserTypes::User => req.header(AUTHORIZATION, user.token.to_string()),
-                        UserTypes::Admin => req.header(AUTHORIZATION, user.token.to_string()),
-                    },
-                    None => req,
-                };
-                match authed_req.send().await {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(Error::RequestFailed(Box::new(e))),
-                }
-            }
-            Err(e) => Err(Error::RequestFailed(Box::new(e))),
-        }
+        let req = self.request(Method::POST, path)?;
+        self.execute(Self::json_body(req, body)).await
     }
 
     pub async fn send_patch<S: AsRef<str>, T: Serialize + Sized>(
@@ -76,46 +132,24 @@ impl PocketBase {
         path: S,
         body: &T,
     ) -> Result<Response> {
-        match self.base_url.join(path.as_ref()) {
-            Ok(endpoint) => {
-                let req = self
-                    .client
-                    .patch(endpoint)
-                    .header(header::CONTENT_TYPE, "application/json")
-                    .body(serde_json::to_string(body).unwrap_or(String::default()));
-                let authed_req = match &self.user {
-                    Some(user) => match &user.usertype {
-                        UserTypes::User => req.header(AUTHORIZATION, user.token.to_string()),
-                        UserTypes::Admin => req.header(AUTHORIZATION, user.token.to_string()),
-                    },
-                    None => req,
-                };
-                match authed_req.send().await {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(Error::RequestFailed(Box::new(e))),
-                }
-            }
-            Err(e) => Err(Error::RequestFailed(Box::new(e))),
-        }
+        let req = self.request(Method::PATCH, path)?;
+        self.execute(Self::json_body(req, body)).await
     }
 
     pub async fn send_delete<S: AsRef<str>>(&self, path: S) -> Result<Response> {
-        match self.base_url.join(path.as_ref()) {
-            Ok(endpoint) => {
-                let req = self.client.delete(endpoint);
-                let authed_req = match &self.user {
-                    Some(user) => match &user.usertype {
-                        UserTypes::User => req.header(AUTHORIZATION, user.token.to_string()),
-                        UserTypes::Admin => req.header(AUTHORIZATION, user.token.to_string()),
-                    },
-                    None => req,
-                };
-                match authed_req.send().await {
-                    Ok(response) => Ok(response),
-                    Err(e) => Err(Error::RequestFailed(Box::new(e))),
-                }
-            }
-            Err(e) => Err(Error::RequestFailed(Box::new(e))),
-        }
+        let req = self.request(Method::DELETE, path)?;
+        self.execute(req).await
+    }
+
+    pub async fn send_multipart<S: AsRef<str>>(&self, path: S, form: Form) -> Result<Response> {
+        let req = self.request(Method::POST, path)?;
+        self.execute(req.multipart(form)).await
+    }
+
+    fn json_body<T: Serialize>(req: RequestBuilder, body: &T) -> RequestBuilder {
+        let body = serde_json::to_string(body).unwrap_or_default();
+        debug!("body={body}");
+        req.header(header::CONTENT_TYPE, "application/json")
+            .body(body)
     }
 }
@@ -0,0 +1,247 @@
+use std::{
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT_LANGUAGE, USER_AGENT},
+    Certificate, Client, Proxy,
+};
+use tokio::sync::watch;
+use url::Url;
+
+use super::{
+    base::SendPipeline, realtime::REALTIME_CONNECT_TIMEOUT, PocketBase, RateLimiter, RetryPolicy,
+};
+use crate::error::{Error, Result};
+
+/// A trusted root certificate, kept as raw bytes so the realtime connection
+/// can use it too. `Pem` may hold a bundle, every certificate in it is trusted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootCertificate {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+}
+
+impl RootCertificate {
+    fn to_reqwest(&self) -> Result<Vec<Certificate>> {
+        match self {
+            RootCertificate::Pem(pem) => Certificate::from_pem_bundle(pem),
+            RootCertificate::Der(der) => Certificate::from_der(der).map(|cert| vec![cert]),
+        }
+        .map_err(|e| Error::InvalidParameter(Box::new(e)))
+    }
+}
+
+/// HTTP settings shared by REST requests and the realtime connection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HttpConfig {
+    /// Sent with every request: user agent, `Accept-Language` and default headers.
+    pub headers: HeaderMap,
+    pub proxy: Option<Url>,
+    pub root_certificates: Vec<RootCertificate>,
+}
+
+/// Builder for [`PocketBase`] when [`PocketBase::new`]'s defaults don't fit.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use pocketbase_sdk_rust::client::PocketBase;
+/// let pb = PocketBase::builder("http://127.0.0.1:8090")
+///     .timeout(Duration::from_secs(30))
+///     .user_agent("my-importer/1.0")
+///     .language("de")
+///     .build()?;
+/// # Ok::<(), pocketbase_sdk_rust::error::Error>(())
+/// ```
+pub struct PocketBaseBuilder {
+    base_url: String,
+    client: Option<Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    language: Option<String>,
+    headers: Vec<(String, String)>,
+    proxy: Option<String>,
+    root_certificates: Vec<RootCertificate>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    realtime_inactivity_timeout: Option<Duration>,
+    realtime_connect_timeout: Option<Duration>,
+}
+
+impl PocketBaseBuilder {
+    pub fn new<S: Into<String>>(base_url: S) -> Self {
+        PocketBaseBuilder {
+            base_url: base_url.into(),
+            client: None,
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            language: None,
+            headers: Vec::new(),
+            proxy: None,
+            root_certificates: Vec::new(),
+            retry_policy: None,
+            rate_limiter: None,
+            realtime_inactivity_timeout: None,
+            realtime_connect_timeout: None,
+        }
+    }
+
+    /// Use a preconfigured client for REST requests. Timeouts, proxy and root
+    /// certificates set on this builder are then not applied to it, headers are.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Total timeout of each REST request. Not applied to the realtime stream.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sent as `Accept-Language`, PocketBase uses it for translated messages.
+    pub fn language<S: Into<String>>(mut self, language: S) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Add a header sent with every request.
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Proxy all traffic through `url`, e.g. `http://proxy:3128`. Without it
+    /// the `HTTP(S)_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables
+    /// apply. Only `http` proxies are supported, the realtime connection
+    /// tunnels through them with `CONNECT`.
+    pub fn proxy<S: Into<String>>(mut self, url: S) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    pub fn add_root_certificate(mut self, certificate: RootCertificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Reconnect the realtime stream when nothing arrived for `timeout`. Pick
+    /// it above the server's ping interval. Off by default.
+    pub fn realtime_inactivity_timeout(mut self, timeout: Duration) -> Self {
+        self.realtime_inactivity_timeout = Some(timeout);
+        self
+    }
+
+    /// How long subscribing waits for the realtime connection to come up,
+    /// 3 seconds by default.
+    pub fn realtime_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.realtime_connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Result<PocketBase> {
+        let base_url =
+            Url::parse(&self.base_url).map_err(|e| Error::InvalidParameter(Box::new(e)))?;
+
+        let mut http = HttpConfig::default();
+        if let Some(user_agent) = &self.user_agent {
+            http.headers.insert(USER_AGENT, header_value(user_agent)?);
+        }
+        if let Some(language) = &self.language {
+            http.headers
+                .insert(ACCEPT_LANGUAGE, header_value(language)?);
+        }
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::InvalidParameter(Box::new(e)))?;
+            http.headers.insert(name, header_value(value)?);
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = Url::parse(proxy).map_err(|e| Error::InvalidParameter(Box::new(e)))?;
+            if proxy.scheme() != "http" {
+                return Err(Error::InvalidParameter(
+                    format!("unsupported proxy scheme {}, use http", proxy.scheme()).into(),
+                ));
+            }
+            http.proxy = Some(proxy);
+        }
+        http.root_certificates = self.root_certificates;
+
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder = Client::builder();
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(proxy) = &http.proxy {
+                    builder = builder.proxy(
+                        Proxy::all(proxy.as_str())
+                            .map_err(|e| Error::InvalidParameter(Box::new(e)))?,
+                    );
+                }
+                for certificate in &http.root_certificates {
+                    for cert in certificate.to_reqwest()? {
+                        builder = builder.add_root_certificate(cert);
+                    }
+                }
+                builder
+                    .build()
+                    .map_err(|e| Error::InvalidParameter(Box::new(e)))?
+            }
+        };
+
+        Ok(PocketBase {
+            base_url,
+            user: None,
+            client,
+            http,
+            subscription: Default::default(),
+            realtime: None,
+            realtime_hooks: Default::default(),
+            realtime_state: Default::default(),
+            realtime_auth: watch::Sender::new(None),
+            server_generation: Default::default(),
+            pending_requests: Default::default(),
+            pipeline: Arc::new(StdMutex::new(SendPipeline {
+                retry_policy: self.retry_policy,
+                rate_limiter: self.rate_limiter.map(Arc::new),
+                ..Default::default()
+            })),
+            realtime_inactivity_timeout: self.realtime_inactivity_timeout,
+            realtime_connect_timeout: self
+                .realtime_connect_timeout
+                .unwrap_or(REALTIME_CONNECT_TIMEOUT),
+        })
+    }
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|e| Error::InvalidParameter(Box::new(e)))
+}
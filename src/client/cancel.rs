@@ -0,0 +1,118 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use futures::{
+    future::{AbortHandle, Abortable, Aborted},
+    Future,
+};
+
+use super::PocketBase;
+use crate::error::{Error, Result};
+
+#[derive(Default)]
+pub(crate) struct PendingRequests {
+    next_id: u64,
+    by_key: HashMap<String, (u64, AbortHandle)>,
+}
+
+// Drops the key's entry once its request settles, unless a newer request
+// already took the key over.
+struct PendingGuard<'a> {
+    pending: &'a Mutex<PendingRequests>,
+    key: String,
+    id: u64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        let mut pending = self.pending.lock().unwrap();
+        if matches!(pending.by_key.get(&self.key), Some((id, _)) if *id == self.id) {
+            pending.by_key.remove(&self.key);
+        }
+    }
+}
+
+impl PocketBase {
+    /// Run `request` under `key`. Starting another request with the same key
+    /// aborts this one, which then resolves to [`Error::Cancelled`].
+    ///
+    /// ```no_run
+    /// # use pocketbase_sdk_rust::client::PocketBase;
+    /// # async fn search(pb: &PocketBase, term: &str) -> pocketbase_sdk_rust::error::Result<()> {
+    /// let filter = format!("title ~ '{term}'");
+    /// let posts = pb
+    ///     .with_request_key(
+    ///         "posts-search",
+    ///         pb.get_list::<_, serde_json::Value>("posts", None, None, None, Some(filter.as_str()), None),
+    ///     )
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_request_key<K, F, T>(&self, key: K, request: F) -> Result<T>
+    where
+        K: Into<String>,
+        F: Future<Output = Result<T>>,
+    {
+        let key = key.into();
+        let (handle, registration) = AbortHandle::new_pair();
+        let id = {
+            let mut pending = self.pending_requests.lock().unwrap();
+            pending.next_id += 1;
+            let id = pending.next_id;
+            if let Some((_, previous)) = pending.by_key.insert(key.clone(), (id, handle)) {
+                previous.abort();
+            }
+            id
+        };
+        let _guard = PendingGuard {
+            pending: &self.pending_requests,
+            key,
+            id,
+        };
+
+        match Abortable::new(request, registration).await {
+            Ok(result) => result,
+            Err(Aborted) => Err(Error::Cancelled),
+        }
+    }
+
+    /// Cancel the in-flight request started under `key`, if any.
+    pub fn cancel_request<K: AsRef<str>>(&self, key: K) {
+        if let Some((_, handle)) = self
+            .pending_requests
+            .lock()
+            .unwrap()
+            .by_key
+            .remove(key.as_ref())
+        {
+            handle.abort();
+        }
+    }
+
+    /// Cancel every in-flight keyed request.
+    pub fn cancel_all(&self) {
+        for (_, (_, handle)) in self.pending_requests.lock().unwrap().by_key.drain() {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future::{self, pending};
+
+    use crate::{client::PocketBase, error::Error};
+
+    #[test]
+    fn test_same_key_cancels_previous() {
+        let pb = PocketBase::new("http://127.0.0.1:8090").unwrap();
+        let (first, second) = futures::executor::block_on(future::join(
+            pb.with_request_key("search", pending::<crate::error::Result<()>>()),
+            pb.with_request_key("search", async { Ok(1) }),
+        ));
+
+        assert!(matches!(first, Err(Error::Cancelled)));
+        assert_eq!(second.unwrap(), 1);
+        assert!(pb.pending_requests.lock().unwrap().by_key.is_empty());
+    }
+}
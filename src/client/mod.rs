@@ -1,40 +1,79 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
 
 use crate::error::Result;
 use crate::{error::Error, user::User};
-use eventsource_client::Event;
 use futures::future::BoxFuture;
 use reqwest::Client;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 use url::Url;
 
-use self::realtime::PocketBaseRealtime;
+use self::base::SendPipeline;
+pub use self::base::{AfterSendHook, BeforeSendHook};
+use self::builder::HttpConfig;
+pub use self::builder::{PocketBaseBuilder, RootCertificate};
+use self::cancel::PendingRequests;
+pub use self::rate_limit::{RateLimit, RateLimiter};
+pub use self::realtime::{
+    ConnectionState, RealtimeMessage, RecordAction, RecordEvent, Resynced, SubscribeOptions,
+};
+use self::realtime::{PocketBaseRealtime, RealtimeHooks, RealtimeState};
+pub use self::retry::RetryPolicy;
+pub use self::send::SendOptions;
+pub use self::server::ServerGeneration;
+pub use self::stream::{OverflowPolicy, RecordStream};
+pub use self::subscription::Subscription;
 
 mod auth;
 mod base;
+mod builder;
+mod cancel;
+mod proxy;
+mod rate_limit;
 mod realtime;
-pub type HashMapSubscription =
-    HashMap<String, Box<dyn Fn(Event) -> BoxFuture<'static, ()> + Send + Sync>>;
+mod retry;
+mod send;
+mod server;
+mod stream;
+mod subscription;
+pub type SubscriptionCallback =
+    Arc<dyn Fn(RealtimeMessage) -> BoxFuture<'static, ()> + Send + Sync>;
+/// Listeners per subscribed topic, keyed by the topic as submitted to the server.
+pub type HashMapSubscription = HashMap<String, Vec<SubscriptionCallback>>;
+/// Called with the topic and the error when a realtime event can't be handled.
+pub type RealtimeErrorCallback = Arc<dyn Fn(String, Error) -> BoxFuture<'static, ()> + Send + Sync>;
+/// Called with the client id on connect, or the reason on disconnect.
+pub type ConnectionCallback = Arc<dyn Fn(String) -> BoxFuture<'static, ()> + Send + Sync>;
+pub type ResyncCallback = Arc<dyn Fn(Resynced) -> BoxFuture<'static, ()> + Send + Sync>;
 
 pub struct PocketBase {
     pub base_url: Url,
     client: Client,
+    http: HttpConfig,
     pub user: Option<User>,
     subscription: Arc<Mutex<HashMapSubscription>>,
     realtime: Option<PocketBaseRealtime>,
+    realtime_hooks: Arc<StdMutex<RealtimeHooks>>,
+    realtime_state: Arc<RealtimeState>,
+    realtime_inactivity_timeout: Option<Duration>,
+    realtime_connect_timeout: Duration,
+    /// Token of `user`, followed by the realtime connection.
+    realtime_auth: watch::Sender<Option<String>>,
+    server_generation: StdMutex<Option<ServerGeneration>>,
+    /// Hooks, retries and rate limiting, shared with the realtime task.
+    pipeline: Arc<StdMutex<SendPipeline>>,
+    pending_requests: StdMutex<PendingRequests>,
 }
 
 impl PocketBase {
     pub fn new<S: AsRef<str>>(raw_url: S) -> Result<PocketBase> {
-        match Url::parse(raw_url.as_ref()) {
-            Ok(url) => Ok(PocketBase {
-                base_url: url,
-                user: None,
-                client: Client::new(),
-                subscription: Default::default(),
-                realtime: None,
-            }),
-            Err(e) => Err(Error::InvalidParameter(Box::new(e))),
-        }
+        PocketBaseBuilder::new(raw_url.as_ref()).build()
+    }
+
+    pub fn builder<S: AsRef<str>>(raw_url: S) -> PocketBaseBuilder {
+        PocketBaseBuilder::new(raw_url.as_ref())
     }
 }
@@ -0,0 +1,132 @@
+use std::{env, net::IpAddr};
+
+use url::{Host, Url};
+
+use super::builder::HttpConfig;
+
+impl HttpConfig {
+    /// The proxy for `url`: the configured one, otherwise the one reqwest
+    /// picks from `HTTP(S)_PROXY`/`ALL_PROXY` unless `NO_PROXY` matches.
+    pub(crate) fn proxy_for(&self, url: &Url) -> Option<Url> {
+        match &self.proxy {
+            Some(proxy) => Some(proxy.clone()),
+            None => env_proxy(url, |name| env::var(name).ok()),
+        }
+    }
+}
+
+fn env_proxy(url: &Url, var: impl Fn(&str) -> Option<String>) -> Option<Url> {
+    let lookup = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| var(name).filter(|value| !value.trim().is_empty()))
+    };
+    let no_proxy = lookup(&["NO_PROXY", "no_proxy"]).unwrap_or_default();
+    if no_proxy_matches(&no_proxy, url) {
+        return None;
+    }
+    let proxy = match url.scheme() {
+        // like reqwest, HTTP_PROXY is ignored in CGI where it's client controlled
+        "http" if var("REQUEST_METHOD").is_some() => None,
+        "http" => lookup(&["HTTP_PROXY", "http_proxy"]),
+        "https" => lookup(&["HTTPS_PROXY", "https_proxy"]),
+        _ => None,
+    }
+    .or_else(|| lookup(&["ALL_PROXY", "all_proxy"]))?;
+    // a bare `host:port` means an HTTP proxy
+    Url::parse(&proxy)
+        .ok()
+        .filter(|proxy| proxy.has_host())
+        .or_else(|| Url::parse(&format!("http://{proxy}")).ok())
+}
+
+/// Whether a `NO_PROXY` list (`*`, domains, IPs or CIDR blocks) covers the
+/// host of `url`. A domain also covers its subdomains.
+fn no_proxy_matches(no_proxy: &str, url: &Url) -> bool {
+    let Some(host) = url.host() else {
+        return false;
+    };
+    no_proxy
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| match host {
+            _ if entry == "*" => true,
+            Host::Domain(domain) => {
+                let entry = entry.trim_start_matches('.');
+                domain == entry || domain.ends_with(&format!(".{entry}"))
+            }
+            Host::Ipv4(ip) => ip_matches(entry, ip.into()),
+            Host::Ipv6(ip) => ip_matches(entry, ip.into()),
+        })
+}
+
+fn ip_matches(entry: &str, ip: IpAddr) -> bool {
+    let (network, prefix) = match entry.split_once('/') {
+        Some((network, prefix)) => (network, prefix.parse::<u32>().ok()),
+        None => (entry, None),
+    };
+    let Ok(network) = network.trim_matches(['[', ']']).parse::<IpAddr>() else {
+        return false;
+    };
+    let (ip, network, bits) = match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            (u32::from(ip) as u128, u32::from(network) as u128, 32)
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => (u128::from(ip), u128::from(network), 128),
+        _ => return false,
+    };
+    let prefix = prefix.unwrap_or(bits).min(bits);
+    let shift = bits - prefix;
+    shift == bits || ip >> shift == network >> shift
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::env_proxy;
+
+    fn proxy(url: &str, vars: &[(&str, &str)]) -> Option<String> {
+        let url = Url::parse(url).unwrap();
+        env_proxy(&url, |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+        .map(|proxy| proxy.to_string())
+    }
+
+    #[test]
+    fn test_env_proxy_by_scheme() {
+        let vars = [
+            ("HTTP_PROXY", "http://plain:3128"),
+            ("https_proxy", "secure:3128"),
+        ];
+        assert_eq!(
+            proxy("http://pb.local/api/realtime", &vars).as_deref(),
+            Some("http://plain:3128/")
+        );
+        assert_eq!(
+            proxy("https://pb.local/api/realtime", &vars).as_deref(),
+            Some("http://secure:3128/")
+        );
+        assert_eq!(
+            proxy("https://pb.local", &[("ALL_PROXY", "http://all:3128")]).as_deref(),
+            Some("http://all:3128/")
+        );
+        assert_eq!(proxy("https://pb.local", &[]), None);
+    }
+
+    #[test]
+    fn test_no_proxy() {
+        let vars = |no_proxy| [("ALL_PROXY", "http://all:3128"), ("NO_PROXY", no_proxy)];
+        assert_eq!(proxy("https://api.pb.local", &vars(".pb.local")), None);
+        assert_eq!(proxy("https://pb.local", &vars("pb.local, other")), None);
+        assert_eq!(proxy("http://10.1.2.3:8090", &vars("10.0.0.0/8")), None);
+        assert_eq!(proxy("http://[::1]:8090", &vars("::1")), None);
+        assert_eq!(proxy("http://127.0.0.1", &vars("*")), None);
+        assert!(proxy("https://notpb.local", &vars("pb.local")).is_some());
+        assert!(proxy("http://11.1.2.3", &vars("10.0.0.0/8")).is_some());
+    }
+}
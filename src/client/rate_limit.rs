@@ -0,0 +1,283 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use reqwest::{Method, Request};
+
+use super::{base::SendPipeline, PocketBase};
+use crate::settings::RateLimitsSettings;
+
+/// `max_requests` per `duration`, the same shape as the server's `rateLimits` rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub duration: Duration,
+}
+
+impl RateLimit {
+    pub fn new(max_requests: u32, duration: Duration) -> Self {
+        RateLimit {
+            max_requests,
+            duration,
+        }
+    }
+
+    /// Whether the limit never hands out a token.
+    fn is_empty(&self) -> bool {
+        self.max_requests == 0
+    }
+}
+
+/// Token bucket refilled continuously, starting full.
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Bucket {
+            limit,
+            tokens: limit.max_requests as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let capacity = self.limit.max_requests as f64;
+        let per_sec = capacity / self.limit.duration.as_secs_f64().max(f64::EPSILON);
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec).min(capacity);
+        self.updated = now;
+    }
+
+    /// Time until a token is available, zero when one is.
+    fn wait_time(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        let per_token = self.limit.duration.as_secs_f64() / self.limit.max_requests as f64;
+        Duration::from_secs_f64((1.0 - self.tokens) * per_token)
+    }
+}
+
+#[derive(Debug)]
+struct PrefixRule {
+    method: Option<Method>,
+    prefix: String,
+    bucket: Bucket,
+}
+
+impl PrefixRule {
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        path.starts_with(&self.prefix)
+            && (self.method.is_none() || self.method.as_ref() == Some(method))
+    }
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    global: Option<Bucket>,
+    // longest prefix first
+    rules: Vec<PrefixRule>,
+}
+
+/// Client-side pacing of outgoing requests.
+///
+/// Every request takes a token from the global bucket and from the most
+/// specific matching prefix bucket, waiting until both have one. Limits
+/// allowing zero requests are ignored rather than blocking forever.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use pocketbase_sdk_rust::client::{RateLimit, RateLimiter};
+/// let limiter = RateLimiter::new()
+///     .global(RateLimit::new(300, Duration::from_secs(10)))
+///     .prefix("POST /api/collections/", RateLimit::new(20, Duration::from_secs(5)));
+/// ```
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn global(self, limit: RateLimit) -> Self {
+        if limit.is_empty() {
+            debug!("Ignoring global rate limit without requests");
+            return self;
+        }
+        self.state.lock().unwrap().global = Some(Bucket::new(limit, Instant::now()));
+        self
+    }
+
+    /// Limit requests whose path starts with `prefix`. Like the server's rule
+    /// labels, the prefix may start with a method, e.g. `POST /api/`.
+    pub fn prefix<S: AsRef<str>>(self, prefix: S, limit: RateLimit) -> Self {
+        if limit.is_empty() {
+            debug!(
+                "Ignoring rate limit without requests for {}",
+                prefix.as_ref()
+            );
+            return self;
+        }
+        let (method, prefix) = match prefix.as_ref().split_once(' ') {
+            Some((method, path)) => (method.parse::<Method>().ok(), path.to_string()),
+            None => (None, prefix.as_ref().to_string()),
+        };
+        {
+            let mut state = self.state.lock().unwrap();
+            state.rules.push(PrefixRule {
+                method,
+                prefix,
+                bucket: Bucket::new(limit, Instant::now()),
+            });
+            state
+                .rules
+                .sort_by_key(|rule| std::cmp::Reverse(rule.prefix.len()));
+        }
+        self
+    }
+
+    /// Mirror the server's path based rules. `collection:action` tag rules
+    /// and audiences have no client-side equivalent and are skipped.
+    pub fn from_settings(settings: &RateLimitsSettings) -> Self {
+        let mut limiter = RateLimiter::new();
+        if settings.enabled == Some(false) {
+            return limiter;
+        }
+        for rule in settings.rules.iter().flatten() {
+            let path = rule.label.rsplit(' ').next().unwrap_or_default();
+            if !path.starts_with('/') {
+                continue;
+            }
+            limiter = limiter.prefix(
+                &rule.label,
+                RateLimit::new(rule.max_requests, Duration::from_secs(rule.duration)),
+            );
+        }
+        limiter
+    }
+
+    /// How long to wait before `method path` may go out, taking the tokens
+    /// when it's zero.
+    fn reserve(&self, method: &Method, path: &str, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let LimiterState { global, rules } = &mut *state;
+        let rule = rules.iter_mut().find(|rule| rule.matches(method, path));
+        let mut buckets: Vec<&mut Bucket> = global
+            .iter_mut()
+            .chain(rule.map(|rule| &mut rule.bucket))
+            .collect();
+
+        let wait = buckets
+            .iter_mut()
+            .map(|bucket| bucket.wait_time(now))
+            .max()
+            .unwrap_or_default();
+        if wait.is_zero() {
+            for bucket in buckets {
+                bucket.tokens -= 1.0;
+            }
+        }
+        wait
+    }
+
+    pub(crate) async fn acquire(&self, method: &Method, path: &str) {
+        loop {
+            let wait = self.reserve(method, path, Instant::now());
+            if wait.is_zero() {
+                return;
+            }
+            debug!("Rate limited {method} {path}, waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl PocketBase {
+    /// Pace outgoing requests client-side, `None` to disable.
+    pub fn set_rate_limiter(&mut self, limiter: Option<RateLimiter>) {
+        self.pipeline.lock().unwrap().rate_limiter = limiter.map(Arc::new);
+    }
+}
+
+impl SendPipeline {
+    pub(crate) async fn pace(&self, request: &Request) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter
+                .acquire(request.method(), request.url().path())
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use reqwest::Method;
+
+    use super::{RateLimit, RateLimiter};
+
+    #[test]
+    fn test_bucket_paces_after_burst() {
+        let limiter = RateLimiter::new().global(RateLimit::new(2, Duration::from_secs(1)));
+        let now = Instant::now();
+
+        assert!(limiter.reserve(&Method::GET, "/api/health", now).is_zero());
+        assert!(limiter.reserve(&Method::GET, "/api/health", now).is_zero());
+        assert_eq!(
+            limiter.reserve(&Method::GET, "/api/health", now),
+            Duration::from_millis(500)
+        );
+        assert!(limiter
+            .reserve(
+                &Method::GET,
+                "/api/health",
+                now + Duration::from_millis(500)
+            )
+            .is_zero());
+    }
+
+    #[test]
+    fn test_most_specific_prefix_applies() {
+        let limiter = RateLimiter::new()
+            .prefix("/api/", RateLimit::new(100, Duration::from_secs(1)))
+            .prefix(
+                "POST /api/collections/",
+                RateLimit::new(1, Duration::from_secs(1)),
+            );
+        let now = Instant::now();
+
+        assert!(limiter
+            .reserve(&Method::POST, "/api/collections/posts/records", now)
+            .is_zero());
+        assert!(!limiter
+            .reserve(&Method::POST, "/api/collections/posts/records", now)
+            .is_zero());
+        assert!(limiter
+            .reserve(&Method::GET, "/api/collections/posts/records", now)
+            .is_zero());
+    }
+
+    #[test]
+    fn test_zero_capacity_limit_is_ignored() {
+        let limiter = RateLimiter::new()
+            .global(RateLimit::new(0, Duration::from_secs(1)))
+            .prefix("/api/", RateLimit::new(0, Duration::from_secs(1)));
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.reserve(&Method::GET, "/api/health", now).is_zero());
+        }
+    }
+}
@@ -1,44 +1,482 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::{Duration, Instant},
+};
 
-use es::{Client, Event, SSE};
+use chrono::{DateTime, Utc};
+use es::{BackoffRetry, Client, Event, RetryStrategy, SSE};
 use eventsource_client as es;
 use futures::{Future, TryStreamExt};
-use log::debug;
-use reqwest::{header, Url};
+use log::{debug, warn};
+use reqwest::{header, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
-use tokio::{
-    sync::{
-        mpsc::{self, UnboundedSender},
-        Mutex,
-    },
-    task::JoinHandle,
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    oneshot, watch, Mutex,
 };
+use url::form_urlencoded;
 
 use crate::error::{Error, Result};
 
-use super::{HashMapSubscription, PocketBase};
+use super::{
+    base::SendPipeline,
+    builder::{HttpConfig, RootCertificate},
+    send::decode_response,
+    subscription::ResyncSlot,
+    ConnectionCallback, HashMapSubscription, PocketBase, RealtimeErrorCallback, ResyncCallback,
+    ServerGeneration, Subscription, SubscriptionCallback,
+};
+
+/// Instructions for the running realtime task.
+#[derive(Debug)]
+pub(crate) enum RealtimeCommand {
+    /// Send the current topics again after a topic went away, or disconnect
+    /// when none is left. The sender is notified when done.
+    Resubmit(Option<oneshot::Sender<()>>),
+    Shutdown,
+}
+
+/// State of the realtime connection, see [`PocketBase::connection_state`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    /// The server assigned `client_id` and got the subscriptions.
+    Connected {
+        client_id: String,
+    },
+    /// The connection was lost, attempt number `attempt` starts after `delay`.
+    Reconnecting {
+        attempt: u32,
+        delay: Duration,
+    },
+    /// Closed for good, until the next subscription.
+    Disconnected {
+        reason: String,
+    },
+}
+
+impl Default for ConnectionState {
+    fn default() -> Self {
+        ConnectionState::Disconnected {
+            reason: "not connected".to_string(),
+        }
+    }
+}
+
+/// [`ConnectionState`] shared with [`PocketBase`]. Each connection task gets
+/// a generation and only the newest one may write, so a task that is still
+/// winding down can't overwrite the state of its successor.
+#[derive(Default)]
+pub(crate) struct RealtimeState {
+    sender: watch::Sender<ConnectionState>,
+    generation: AtomicU64,
+}
+
+impl RealtimeState {
+    /// Hand the state to a new connection task, returning its generation.
+    fn start(&self) -> u64 {
+        let mut generation = 0;
+        // bump under the watch lock so no older task writes in between
+        self.sender.send_modify(|state| {
+            generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+            *state = ConnectionState::Connecting;
+        });
+        generation
+    }
+
+    /// Publish `state` unless a newer connection task took over, returning
+    /// whether it was published.
+    fn publish(&self, generation: u64, state: ConnectionState) -> bool {
+        self.sender.send_if_modified(|current| {
+            if self.generation.load(Ordering::SeqCst) != generation {
+                return false;
+            }
+            *current = state;
+            true
+        })
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ConnectionState> {
+        self.sender.subscribe()
+    }
+}
+
+/// The realtime connection was re-established after being lost from
+/// `gap_started` to `gap_ended`; record events of that window were missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resynced {
+    pub gap_started: DateTime<Utc>,
+    pub gap_ended: DateTime<Utc>,
+}
+
+impl Resynced {
+    /// Filter for records changed since the gap started, to catch up with
+    /// e.g. [`PocketBase::get_list`].
+    pub fn filter(&self) -> String {
+        format!(
+            "updated >= \"{}\"",
+            self.gap_started.format("%Y-%m-%d %H:%M:%S%.3fZ")
+        )
+    }
+}
+
+/// What a listener registered for a topic is handed.
+#[derive(Debug, Clone)]
+pub enum RealtimeMessage {
+    /// A server event on the topic.
+    Event(Event),
+    /// The connection came back from an outage, events of the topic in that
+    /// window were missed. Goes to every listener.
+    Resynced(Resynced),
+    /// The listener was unsubscribed or the connection closed for good, no
+    /// more messages follow.
+    Closed,
+}
+
+/// Callbacks registered through `on_realtime_error`, `on_connect`,
+/// `on_disconnect` and `on_resync`.
+#[derive(Default, Clone)]
+pub(crate) struct RealtimeHooks {
+    pub error: Option<RealtimeErrorCallback>,
+    pub connect: Option<ConnectionCallback>,
+    pub disconnect: Option<ConnectionCallback>,
+    pub resync: Option<ResyncCallback>,
+}
+
+/// Push the inactivity deadline back by the time spent since `busy` in
+/// callbacks or requests, during which the stream wasn't read.
+fn extend_deadline(deadline: &mut Option<tokio::time::Instant>, busy: tokio::time::Instant) {
+    if let Some(deadline) = deadline {
+        *deadline += busy.elapsed();
+    }
+}
+
+/// Why one SSE stream was given up.
+enum SessionEnd {
+    Reconnect(String),
+    Close(String),
+}
+
+/// The realtime task: keeps an SSE stream up, reconnecting with backoff, and
+/// dispatches its events.
+struct Connection {
+    url: String,
+    id: Arc<Mutex<String>>,
+    auth: watch::Receiver<Option<String>>,
+    callbacks: Arc<Mutex<HashMapSubscription>>,
+    commands: UnboundedReceiver<RealtimeCommand>,
+    client: reqwest::Client,
+    pipeline: Arc<StdMutex<SendPipeline>>,
+    http: HttpConfig,
+    state: Arc<RealtimeState>,
+    /// Which writer of `state` this task is.
+    generation: u64,
+    /// Whether the current stream got its `PB_CONNECT`.
+    connected: bool,
+    hooks: Arc<StdMutex<RealtimeHooks>>,
+    inactivity_timeout: Option<Duration>,
+    /// When the last established stream was lost, until the next `PB_CONNECT`.
+    gap_started: Option<DateTime<Utc>>,
+}
+
+impl Connection {
+    async fn run(mut self) {
+        // built once, its TLS setup loads the system certificates
+        let client = match self.sse_client() {
+            Ok(client) => client,
+            Err(e) => {
+                self.close(format!("invalid realtime request: {e}")).await;
+                return;
+            }
+        };
+        let mut backoff =
+            BackoffRetry::new(Duration::from_secs(1), Duration::from_secs(60), 2, true);
+        let mut attempt = 0;
+        loop {
+            let end = self.session(&client, &mut backoff, &mut attempt).await;
+            let was_connected = std::mem::take(&mut self.connected);
+            self.id.lock().await.clear();
+            let (reason, close) = match end {
+                SessionEnd::Reconnect(reason) => (reason, false),
+                SessionEnd::Close(reason) => (reason, true),
+            };
+            if was_connected {
+                self.gap_started = Some(Utc::now());
+                let disconnect = self.hooks.lock().unwrap().disconnect.clone();
+                if let Some(disconnect) = disconnect {
+                    disconnect(reason.clone()).await;
+                }
+            }
+            if close {
+                self.close(reason).await;
+                return;
+            }
+
+            attempt += 1;
+            let delay = backoff.next_delay(Instant::now());
+            debug!("Realtime lost ({reason}), reconnect in {delay:?} (attempt {attempt})");
+            self.set_state(ConnectionState::Reconnecting { attempt, delay });
+            let wake = tokio::time::Instant::now() + delay;
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(wake) => break,
+                    command = self.commands.recv() => {
+                        if let Some(SessionEnd::Close(reason)) = self.command(command).await {
+                            self.close(reason).await;
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn session(
+        &mut self,
+        client: &impl Client,
+        backoff: &mut BackoffRetry,
+        attempt: &mut u32,
+    ) -> SessionEnd {
+        let mut stream = client.stream();
+        // anything from the server, pings included, counts as activity
+        let inactivity = self.inactivity_timeout;
+        let mut idle_deadline = inactivity.map(|window| tokio::time::Instant::now() + window);
+        loop {
+            tokio::select! {
+                next_stream = stream.try_next() => {
+                    let busy = tokio::time::Instant::now();
+                    if let (Ok(Some(_)), Some(window)) = (&next_stream, inactivity) {
+                        idle_deadline = Some(busy + window);
+                    }
+                    match next_stream {
+                        Ok(Some(SSE::Event(event))) if event.event_type == "PB_CONNECT" => {
+                            let Some(client_id) = event.id.clone() else {
+                                continue;
+                            };
+                            *self.id.lock().await = client_id.clone();
+                            self.submit().await;
+                            backoff.reset(Instant::now());
+                            *attempt = 0;
+                            self.connected = true;
+                            self.set_state(ConnectionState::Connected {
+                                client_id: client_id.clone(),
+                            });
+                            let (connect, resync) = {
+                                let hooks = self.hooks.lock().unwrap();
+                                (hooks.connect.clone(), hooks.resync.clone())
+                            };
+                            if let Some(connect) = connect {
+                                connect(client_id).await;
+                            }
+                            if let Some(gap_started) = self.gap_started.take() {
+                                let resynced = Resynced {
+                                    gap_started,
+                                    gap_ended: Utc::now(),
+                                };
+                                debug!("Realtime resynced {resynced:?}");
+                                if let Some(resync) = resync {
+                                    resync(resynced).await;
+                                }
+                                self.dispatch(RealtimeMessage::Resynced(resynced)).await;
+                            }
+                        }
+                        Ok(Some(SSE::Event(event))) => {
+                            self.dispatch(RealtimeMessage::Event(event)).await
+                        }
+                        Ok(Some(SSE::Comment(comment))) => debug!("We got comment: {comment}"),
+                        Ok(None) => return SessionEnd::Reconnect("stream ended".to_string()),
+                        Err(e) => return SessionEnd::Reconnect(e.to_string()),
+                    }
+                    extend_deadline(&mut idle_deadline, busy);
+                }
+                _ = tokio::time::sleep_until(idle_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                    if idle_deadline.is_some() =>
+                {
+                    let window = inactivity.unwrap_or_default();
+                    return SessionEnd::Reconnect(format!("no activity within {window:?}"));
+                }
+                command = self.commands.recv() => {
+                    let busy = tokio::time::Instant::now();
+                    if let Some(end) = self.command(command).await {
+                        return end;
+                    }
+                    extend_deadline(&mut idle_deadline, busy);
+                }
+                changed = self.auth.changed() => {
+                    if changed.is_err() {
+                        return SessionEnd::Close("client dropped".to_string());
+                    }
+                    let busy = tokio::time::Instant::now();
+                    if self.submit().await == Some(StatusCode::FORBIDDEN) {
+                        // the server keeps a connection bound to the auth record
+                        // it started with, switching users needs a new one
+                        return SessionEnd::Reconnect("auth changed".to_string());
+                    }
+                    extend_deadline(&mut idle_deadline, busy);
+                }
+            }
+        }
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        self.state.publish(self.generation, state);
+    }
+
+    /// Disconnect for good and tell the listeners, unless a newer connection
+    /// took them over.
+    async fn close(&self, reason: String) {
+        debug!("Realtime closed: {reason}");
+        let listeners = {
+            // `add_listener` starts a new connection under this lock
+            let callbacks_locked = self.callbacks.lock().await;
+            if !self
+                .state
+                .publish(self.generation, ConnectionState::Disconnected { reason })
+            {
+                return;
+            }
+            callbacks_locked
+                .values()
+                .flat_map(|listeners| listeners.iter().cloned())
+                .collect::<Vec<_>>()
+        };
+        for callback in listeners {
+            callback(RealtimeMessage::Closed).await;
+        }
+    }
+
+    fn sse_client(&self) -> es::Result<impl Client> {
+        let mut builder = es::ClientBuilder::for_url(self.url.as_str())?;
+        for (name, value) in &self.http.headers {
+            match value.to_str() {
+                Ok(value) => builder = builder.header(name.as_str(), value)?,
+                Err(_) => debug!("Skip non visible ASCII header {name} for SSE"),
+            }
+        }
+        // the proxy reqwest uses for REST, the environment's included
+        let proxy = Url::parse(&self.url)
+            .ok()
+            .and_then(|url| self.http.proxy_for(&url));
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy.as_str())?;
+        }
+        for certificate in &self.http.root_certificates {
+            builder = match certificate {
+                RootCertificate::Pem(pem) => builder.add_root_certificates_pem(pem)?,
+                RootCertificate::Der(der) => builder.add_root_certificate(der.clone()),
+            };
+        }
+        if let Some(window) = self.inactivity_timeout {
+            // catches a connection stuck mid read, e.g. half-open
+            builder = builder.read_timeout(window);
+        }
+        // reconnecting is up to `run`, which drops the stream on the first error
+        Ok(builder
+            .method("GET".to_string())
+            .reconnect(es::ReconnectOptions::reconnect(false).build())
+            .build())
+    }
+
+    /// `Some` when the command ends the task.
+    async fn command(&mut self, command: Option<RealtimeCommand>) -> Option<SessionEnd> {
+        let Some(RealtimeCommand::Resubmit(ack)) = command else {
+            return Some(SessionEnd::Close("shutdown".to_string()));
+        };
+        {
+            // close under the lock so `add_listener` sees either a running
+            // task or a closed channel
+            let sub_locked = self.callbacks.lock().await;
+            if sub_locked.is_empty() {
+                self.commands.close();
+            }
+        }
+        self.submit().await;
+        if let Some(ack) = ack {
+            let _ = ack.send(());
+        }
+        if self.commands.is_closed() {
+            return Some(SessionEnd::Close("nothing subscribed".to_string()));
+        }
+        None
+    }
+
+    async fn submit(&mut self) -> Option<StatusCode> {
+        let token = self.auth.borrow_and_update().clone();
+        post_subscriptions(
+            &self.client,
+            &self.pipeline,
+            &self.url,
+            &self.http,
+            token,
+            &self.id,
+            &self.callbacks,
+        )
+        .await
+    }
+
+    async fn dispatch(&self, message: RealtimeMessage) {
+        let listeners = {
+            let callbacks_locked = self.callbacks.lock().await;
+            callbacks_locked
+                .iter()
+                .filter(|(topic, _)| match &message {
+                    RealtimeMessage::Event(event) => topic_matches(topic, &event.event_type),
+                    RealtimeMessage::Resynced(_) | RealtimeMessage::Closed => true,
+                })
+                .flat_map(|(_, listeners)| listeners.iter().cloned())
+                .collect::<Vec<_>>()
+        };
+        for callback in listeners {
+            callback(message.clone()).await;
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct PocketBaseRealtime {
     id: Arc<Mutex<String>>,
-    token: String,
+    auth: watch::Receiver<Option<String>>,
     endpont: Url,
     callbacks: Arc<Mutex<HashMapSubscription>>,
-    shutdown_send: Option<UnboundedSender<bool>>,
+    commands: Option<UnboundedSender<RealtimeCommand>>,
+    client: reqwest::Client,
+    pipeline: Arc<StdMutex<SendPipeline>>,
+    http: HttpConfig,
+    state: Arc<RealtimeState>,
+    hooks: Arc<StdMutex<RealtimeHooks>>,
+    inactivity_timeout: Option<Duration>,
 }
 
 impl PocketBaseRealtime {
-    pub fn new<S: AsRef<str>>(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
         endpont: Url,
-        token: S,
+        auth: watch::Receiver<Option<String>>,
         callbacks: Arc<Mutex<HashMapSubscription>>,
+        client: reqwest::Client,
+        pipeline: Arc<StdMutex<SendPipeline>>,
+        http: HttpConfig,
+        state: Arc<RealtimeState>,
+        hooks: Arc<StdMutex<RealtimeHooks>>,
+        inactivity_timeout: Option<Duration>,
     ) -> Self {
         PocketBaseRealtime {
             id: Default::default(),
             endpont,
             callbacks,
-            shutdown_send: None,
-            token: token.as_ref().to_string(),
+            commands: None,
+            auth,
+            client,
+            pipeline,
+            http,
+            state,
+            hooks,
+            inactivity_timeout,
         }
     }
 
@@ -46,105 +484,75 @@ impl PocketBaseRealtime {
         self.id.lock().await.to_string()
     }
 
-    fn start_connection(&mut self) -> JoinHandle<std::result::Result<(), es::Error>> {
-        let url = self.endpont.to_string();
-        let callbacks = self.callbacks.clone();
-        let id = self.id.clone();
-        let token = self.token.clone();
-        let (shutdown_send, mut shutdown_recv) = mpsc::unbounded_channel::<bool>();
-        self.shutdown_send = Some(shutdown_send);
-        tokio::spawn(async move {
-            let client = es::ClientBuilder::for_url(url.as_str())?
-                .method("GET".to_string())
-                .reconnect(
-                    es::ReconnectOptions::reconnect(true)
-                        .retry_initial(false)
-                        .delay(Duration::from_secs(1))
-                        .backoff_factor(2)
-                        .delay_max(Duration::from_secs(60))
-                        .build(),
-                )
-                .build_http();
+    pub(crate) fn commands(&self) -> Option<UnboundedSender<RealtimeCommand>> {
+        self.commands.clone()
+    }
 
-            let mut stream = client.stream();
-            loop {
-                tokio::select! {
-                    next_stream = stream.try_next() => {
-                        if let Ok(Some(event)) = next_stream {
-                            match event {
-                                SSE::Event(real_event) => {
-                                    if real_event.event_type == "PB_CONNECT" {
-                                        if let Some(event_id) = &real_event.id {
-                                            let new_id = {
-                                                let mut id_unlocked = id.lock().await;
-                                                *id_unlocked = event_id.to_string();
-                                                (*id_unlocked).clone()
-                                            };
-                                            let params = {
-                                                let keys = {
-                                                    let sub_locked = callbacks.lock().await;
-                                                    sub_locked.keys().cloned().collect::<Vec<String>>()
-                                                };
-                                                let mut params = HashMap::new();
-                                                params.insert("clientId".to_string(), json!(new_id.clone()));
-                                                params.insert("subscriptions".to_string(), json!(keys));
-                                                params
-                                            };
-
-                                            let client = reqwest::Client::new();
-                                            let req = client.post(url.as_str())
-                                                .body(serde_json::to_string(&params).unwrap_or(Default::default()))
-                                                .header(header::CONTENT_TYPE, "application/json")
-                                                .header(header::AUTHORIZATION, token.clone());
-
-
-                                            let _ = req.send().await;
-
-                                            continue;
-                                        }
-                                    }
-                                    let callbacks_locked = callbacks.lock().await;
-                                    for callback in callbacks_locked.values() {
-                                        callback(real_event.clone()).await;
-                                    }
-                                }
-                                SSE::Comment(command) => {
-                                    debug!("We got command: {command}");
-                                }
-                            }
-                        }
-                    }
-                    _ = shutdown_recv.recv() => {
-                        debug!("Shutdown");
-                        return Ok(())
-                    }
-                }
-            }
-        })
+    /// Whether the connection task is still around to take commands.
+    pub(crate) fn is_running(&self) -> bool {
+        self.commands.as_ref().is_some_and(|c| !c.is_closed())
     }
 
-    pub async fn ensure_connected(&mut self, timeout: Duration) -> Result<String> {
-        self.start_connection();
+    fn start_connection(&mut self, generation: u64) {
+        let (commands, commands_recv) = mpsc::unbounded_channel::<RealtimeCommand>();
+        self.commands = Some(commands);
+        let connection = Connection {
+            url: self.endpont.to_string(),
+            id: self.id.clone(),
+            auth: self.auth.clone(),
+            callbacks: self.callbacks.clone(),
+            commands: commands_recv,
+            client: self.client.clone(),
+            pipeline: self.pipeline.clone(),
+            http: self.http.clone(),
+            state: self.state.clone(),
+            generation,
+            connected: false,
+            hooks: self.hooks.clone(),
+            inactivity_timeout: self.inactivity_timeout,
+            gap_started: None,
+        };
+        tokio::spawn(connection.run());
+    }
 
-        let now = std::time::Instant::now();
-        while std::time::Instant::now() - now < timeout {
-            {
-                if let Ok(id_unlocked) = self.id.try_lock() {
-                    if !id_unlocked.is_empty() {
-                        return Ok(id_unlocked.to_string());
-                    }
-                }
-            }
+    /// Connect as `generation` of the shared state and wait for the client id,
+    /// giving up after `timeout`.
+    pub(crate) async fn ensure_connected(
+        &mut self,
+        generation: u64,
+        timeout: Duration,
+    ) -> Result<String> {
+        self.start_connection(generation);
 
-            tokio::time::sleep(Duration::from_millis(10)).await;
-        }
+        let mut state = self.state.subscribe();
+        let settled = match tokio::time::timeout(
+            timeout,
+            state.wait_for(|state| {
+                matches!(
+                    state,
+                    ConnectionState::Connected { .. } | ConnectionState::Disconnected { .. }
+                )
+            }),
+        )
+        .await
+        {
+            Ok(Ok(state)) => Some(state.clone()),
+            _ => None,
+        };
+        let result = match settled {
+            Some(ConnectionState::Connected { client_id }) => return Ok(client_id),
+            Some(ConnectionState::Disconnected { reason }) => Err(Error::RequestFailed(
+                format!("Realtime disconnected: {reason}").into(),
+            )),
+            _ => Err(Error::Timeout("Overtime wait SSE Id".to_string())),
+        };
         self.shutdown();
-        Err(Error::Timeout("Overtime wait SSE Id".to_string()))
+        result
     }
 
     fn shutdown(&self) {
-        if let Some(shutdown) = &self.shutdown_send {
-            let _ = shutdown.send(true);
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(RealtimeCommand::Shutdown);
         }
     }
 }
@@ -155,72 +563,471 @@ impl Drop for PocketBaseRealtime {
     }
 }
 
+/// Submit the current topics for the connection `id`, anonymously when there
+/// is no token, through the client's send pipeline. Returns the response
+/// status, if any.
+async fn post_subscriptions(
+    http_client: &reqwest::Client,
+    pipeline: &StdMutex<SendPipeline>,
+    url: &str,
+    http: &HttpConfig,
+    token: Option<String>,
+    id: &Mutex<String>,
+    callbacks: &Mutex<HashMapSubscription>,
+) -> Option<StatusCode> {
+    let client_id = id.lock().await.clone();
+    if client_id.is_empty() {
+        return None;
+    }
+    let keys = {
+        let sub_locked = callbacks.lock().await;
+        sub_locked.keys().cloned().collect::<Vec<String>>()
+    };
+    let mut params = HashMap::new();
+    params.insert("clientId".to_string(), json!(client_id));
+    params.insert("subscriptions".to_string(), json!(keys));
+
+    let mut req = http_client
+        .post(url)
+        .headers(http.headers.clone())
+        .body(serde_json::to_string(&params).unwrap_or_default())
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        req = req.header(header::AUTHORIZATION, token);
+    }
+    let request = match req.build() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Submitting realtime subscriptions failed: {e}");
+            return None;
+        }
+    };
+    // same hooks, rate limiter and retries as `PocketBase::execute`
+    let pipeline = pipeline.lock().unwrap().clone();
+    match pipeline.execute(http_client, request).await {
+        Ok(response) => {
+            if !response.status().is_success() {
+                warn!(
+                    "Submitting realtime subscriptions failed with {}",
+                    response.status()
+                );
+            }
+            Some(response.status())
+        }
+        Err(e) => {
+            warn!("Submitting realtime subscriptions failed: {e}");
+            None
+        }
+    }
+}
+
+/// Split a topic key into the topic and its `?options=...` suffix.
+fn split_topic(topic: &str) -> (&str, &str) {
+    match topic.find('?') {
+        Some(at) => topic.split_at(at),
+        None => (topic, ""),
+    }
+}
+
+/// `collection` and `collection/*` both name every record of the collection.
+fn canonical_topic(topic: &str) -> (&str, &str) {
+    let (base, options) = split_topic(topic);
+    (base.strip_suffix("/*").unwrap_or(base), options)
+}
+
+/// Whether an event named `event_name` belongs to listeners of `topic`.
+fn topic_matches(topic: &str, event_name: &str) -> bool {
+    canonical_topic(topic) == canonical_topic(event_name)
+}
+
+/// Per subscription query parameters and headers, applied by the server when
+/// it checks access to and serializes each record event.
+///
+/// ```
+/// # use pocketbase_sdk_rust::client::SubscribeOptions;
+/// let options = SubscribeOptions::new()
+///     .filter("status = 'published'")
+///     .expand("author");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SubscribeOptions {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub query: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl SubscribeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter<S: Into<String>>(self, filter: S) -> Self {
+        self.query("filter", filter)
+    }
+
+    pub fn expand<S: Into<String>>(self, expand: S) -> Self {
+        self.query("expand", expand)
+    }
+
+    pub fn fields<S: Into<String>>(self, fields: S) -> Self {
+        self.query("fields", fields)
+    }
+
+    pub fn query<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.query.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    fn is_empty(&self) -> bool {
+        self.query.is_empty() && self.headers.is_empty()
+    }
+
+    /// The topic key carrying the options, `topic?options=<json>`. Maps are
+    /// ordered so equal options give the same key.
+    fn apply(&self, topic: String) -> String {
+        if self.is_empty() {
+            return topic;
+        }
+        let options = serde_json::to_string(self).unwrap_or_default();
+        let separator = if topic.contains('?') { '&' } else { '?' };
+        format!(
+            "{topic}{separator}options={}",
+            form_urlencoded::byte_serialize(options.as_bytes()).collect::<String>()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordAction {
+    Create,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordEvent<T> {
+    pub action: RecordAction,
+    pub record: T,
+}
+
 static API_REALTIME: &str = "/api/realtime";
+/// How long the first subscription waits for the realtime connection by default.
+pub(crate) const REALTIME_CONNECT_TIMEOUT: Duration = Duration::from_millis(3000);
 impl PocketBase {
-    pub async fn subscribe<S: AsRef<str>, F, Fut>(
+    /// Listen for record events of `collection` (`record_id` empty or `*` for
+    /// every record). Payloads that don't deserialize into `T` go to the
+    /// [`on_realtime_error`](Self::on_realtime_error) callback.
+    pub async fn subscribe<T, S, F, Fut>(
         &mut self,
         collection: S,
         record_id: S,
         callback: F,
-    ) -> Result<()>
+    ) -> Result<Subscription>
     where
-        F: Fn(Event) -> Fut + Send + Sync + 'static,
+        T: DeserializeOwned + Send + 'static,
+        S: AsRef<str>,
+        F: Fn(RecordEvent<T>) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
-        let subscribe_to = Self::resolve_subscribe_to(collection, record_id);
-        {
+        self.subscribe_with_options(
+            collection,
+            record_id,
+            &SubscribeOptions::default(),
+            callback,
+        )
+        .await
+    }
+
+    /// [`subscribe`](Self::subscribe) with a filter, expand, fields or other
+    /// per subscription options.
+    pub async fn subscribe_with_options<T, S, F, Fut>(
+        &mut self,
+        collection: S,
+        record_id: S,
+        options: &SubscribeOptions,
+        callback: F,
+    ) -> Result<Subscription>
+    where
+        T: DeserializeOwned + Send + 'static,
+        S: AsRef<str>,
+        F: Fn(RecordEvent<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let generation = self.resolve_server_generation().await?;
+        let subscribe_to = options.apply(Self::resolve_subscribe_to(
+            collection, record_id, generation,
+        ));
+        let resync = ResyncSlot::default();
+        let listener = self.typed_listener(subscribe_to.clone(), callback, resync.clone());
+        self.add_listener(subscribe_to, listener, resync).await
+    }
+
+    /// Register `listener` under `topic` and make sure the server knows the
+    /// topic, (re)connecting when needed.
+    pub(crate) async fn add_listener(
+        &mut self,
+        topic: String,
+        listener: SubscriptionCallback,
+        resync: ResyncSlot,
+    ) -> Result<Subscription> {
+        self.sync_realtime_auth();
+        let generation = {
             let mut sub_locked = self.subscription.lock().await;
-            sub_locked.remove(&subscribe_to);
-            sub_locked.insert(
-                subscribe_to.clone(),
-                Box::new(move |event| Box::pin(callback(event))),
-            );
-        }
-        if self.realtime.is_none() {
+            sub_locked
+                .entry(topic.clone())
+                .or_default()
+                .push(listener.clone());
+            // under the lock, so a closing task either closes the listener
+            // before or leaves it to the new connection
+            let running = self.realtime.as_ref().is_some_and(|r| r.is_running());
+            (!running).then(|| self.realtime_state.start())
+        };
+        if let Some(generation) = generation {
             let endpoint = self.base_url.join(API_REALTIME).expect("WTH");
 
             let realtime = PocketBaseRealtime::new(
                 endpoint,
-                self.user.as_ref().unwrap().token.clone(),
+                self.realtime_auth.subscribe(),
                 self.subscription.clone(),
+                self.client.clone(),
+                self.pipeline.clone(),
+                self.http.clone(),
+                self.realtime_state.clone(),
+                self.realtime_hooks.clone(),
+                self.realtime_inactivity_timeout,
             );
             self.realtime = Some(realtime);
 
-            let id = self
+            let connected = self
                 .realtime
                 .as_mut()
                 .unwrap()
-                .ensure_connected(Duration::from_millis(3000))
-                .await?;
-            debug!("We get id {id}");
-        } else {
+                .ensure_connected(generation, self.realtime_connect_timeout)
+                .await;
+            match connected {
+                Ok(id) => debug!("We get id {id}"),
+                Err(e) => {
+                    self.remove_listener(&topic, &listener).await;
+                    return Err(e);
+                }
+            }
+        } else if let Err(e) = self.submit_subscriptions().await {
             // let sse client submit it first time
-            self.submit_subscriptions().await?;
+            self.remove_listener(&topic, &listener).await;
+            return Err(e);
         }
 
-        Ok(())
+        Ok(Subscription::new(
+            topic,
+            listener,
+            self.subscription.clone(),
+            self.realtime.as_ref().and_then(|r| r.commands()),
+            resync,
+        ))
     }
 
+    /// Undo the registration of a listener that never got subscribed.
+    async fn remove_listener(&self, topic: &str, listener: &SubscriptionCallback) {
+        let mut sub_locked = self.subscription.lock().await;
+        if let Some(listeners) = sub_locked.get_mut(topic) {
+            listeners.retain(|l| !Arc::ptr_eq(l, listener));
+            if listeners.is_empty() {
+                sub_locked.remove(topic);
+            }
+        }
+    }
+
+    /// Publish the current token to the realtime connection, which resubmits
+    /// its subscriptions when it changed.
+    pub(crate) fn sync_realtime_auth(&self) {
+        let token = self.user.as_ref().map(|user| user.token.clone());
+        self.realtime_auth.send_if_modified(|current| {
+            if *current == token {
+                return false;
+            }
+            *current = token;
+            true
+        });
+    }
+
+    /// Register the callback for realtime events that can't be handled, e.g.
+    /// payloads that don't match the subscribed type. Replaces the previous one.
+    pub fn on_realtime_error<F, Fut>(&mut self, callback: F)
+    where
+        F: Fn(String, Error) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.realtime_hooks.lock().unwrap().error = Some(Arc::new(move |topic, error| {
+            Box::pin(callback(topic, error))
+        }));
+    }
+
+    /// Called with the client id each time the realtime connection is
+    /// (re)established and the subscriptions were submitted.
+    pub fn on_connect<F, Fut>(&mut self, callback: F)
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.realtime_hooks.lock().unwrap().connect =
+            Some(Arc::new(move |client_id| Box::pin(callback(client_id))));
+    }
+
+    /// Called with the reason when an established realtime connection is lost
+    /// or closed.
+    pub fn on_disconnect<F, Fut>(&mut self, callback: F)
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.realtime_hooks.lock().unwrap().disconnect =
+            Some(Arc::new(move |reason| Box::pin(callback(reason))));
+    }
+
+    /// Called after the realtime connection came back from an outage, with the
+    /// window in which record events were missed. Each subscription is told as
+    /// well, see [`Subscription::on_resync`]. Refetch what's needed:
+    ///
+    /// ```no_run
+    /// # use pocketbase_sdk_rust::client::PocketBase;
+    /// # async fn run(mut pb: PocketBase) -> pocketbase_sdk_rust::error::Result<()> {
+    /// let (gaps, mut gap_recv) = tokio::sync::mpsc::unbounded_channel();
+    /// pb.on_resync(move |resynced| {
+    ///     let _ = gaps.send(resynced);
+    ///     async {}
+    /// });
+    /// while let Some(resynced) = gap_recv.recv().await {
+    ///     let filter = resynced.filter();
+    ///     let _missed = pb
+    ///         .get_list::<_, serde_json::Value>("posts", None, None, None, Some(filter.as_str()), None)
+    ///         .await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_resync<F, Fut>(&mut self, callback: F)
+    where
+        F: Fn(Resynced) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.realtime_hooks.lock().unwrap().resync =
+            Some(Arc::new(move |resynced| Box::pin(callback(resynced))));
+    }
+
+    /// Reconnect the realtime stream when nothing, not even a ping, arrived
+    /// for `timeout`; `None` to wait forever. Applies from the next connection.
+    pub fn set_realtime_inactivity_timeout(&mut self, timeout: Option<Duration>) {
+        self.realtime_inactivity_timeout = timeout;
+    }
+
+    /// How long subscribing waits for the realtime connection to come up.
+    pub fn set_realtime_connect_timeout(&mut self, timeout: Duration) {
+        self.realtime_connect_timeout = timeout;
+    }
+
+    /// Follow the realtime connection: connecting, connected, reconnecting or
+    /// disconnected.
+    pub fn connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.realtime_state.subscribe()
+    }
+
+    pub(crate) fn typed_listener<T, F, Fut>(
+        &self,
+        topic: String,
+        callback: F,
+        resync: ResyncSlot,
+    ) -> SubscriptionCallback
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: Fn(RecordEvent<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let hooks = self.realtime_hooks.clone();
+        Arc::new(move |message: RealtimeMessage| {
+            let event = match message {
+                RealtimeMessage::Event(event) => event,
+                RealtimeMessage::Resynced(resynced) => {
+                    let on_resync = resync.lock().unwrap().clone();
+                    return match on_resync {
+                        Some(on_resync) => on_resync(resynced),
+                        None => Box::pin(async {}),
+                    };
+                }
+                RealtimeMessage::Closed => return Box::pin(async {}),
+            };
+            match serde_json::from_str(&event.data) {
+                Ok(record_event) => Box::pin(callback(record_event)),
+                Err(e) => {
+                    let error = Error::RequestFailed(Box::new(e));
+                    let on_error = hooks.lock().unwrap().error.clone();
+                    match on_error {
+                        Some(on_error) => on_error(topic.clone(), error),
+                        None => {
+                            warn!("Dropped realtime event for {topic}: {error}");
+                            Box::pin(async {})
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Drop every listener of the topic, whatever its options, or of all topics
+    /// when `collection` is empty. Disconnects once nothing is subscribed.
     pub async fn unsubscribe<S: AsRef<str>>(&mut self, collection: S, record_id: S) -> Result<()> {
-        let subscribe_to = Self::resolve_subscribe_to(collection, record_id);
-        {
+        let subscribe_to = if collection.as_ref().is_empty() {
+            String::new()
+        } else {
+            let generation = self.resolve_server_generation().await?;
+            Self::resolve_subscribe_to(collection, record_id, generation)
+        };
+        let (removed, nothing_left) = {
             let mut sub_locked = self.subscription.lock().await;
-            if subscribe_to.is_empty() {
-                sub_locked.clear();
-            } else {
-                sub_locked.remove(&subscribe_to);
-            }
+            let topics = sub_locked
+                .keys()
+                .filter(|topic| subscribe_to.is_empty() || split_topic(topic).0 == subscribe_to)
+                .cloned()
+                .collect::<Vec<_>>();
+            let removed = topics
+                .iter()
+                .filter_map(|topic| sub_locked.remove(topic))
+                .flatten()
+                .collect::<Vec<_>>();
+            (removed, sub_locked.is_empty())
+        };
+        for listener in removed {
+            listener(RealtimeMessage::Closed).await;
         }
         self.submit_subscriptions().await?;
+        if nothing_left {
+            self.realtime = None;
+        }
         Ok(())
     }
 
+    /// The whole-collection topic is `collection` up to 0.22 and
+    /// `collection/*` since.
     #[inline]
-    fn resolve_subscribe_to<S: AsRef<str>>(collection: S, record_id: S) -> String {
+    pub(crate) fn resolve_subscribe_to<S: AsRef<str>>(
+        collection: S,
+        record_id: S,
+        generation: ServerGeneration,
+    ) -> String {
+        let collection = collection.as_ref().trim_end_matches("/*");
         if record_id.as_ref().is_empty() || record_id.as_ref() == "*" {
-            collection.as_ref().to_string()
+            match generation {
+                ServerGeneration::Legacy => collection.to_string(),
+                ServerGeneration::Superusers => format!("{collection}/*"),
+            }
         } else {
-            format!("{}/{}", collection.as_ref(), record_id.as_ref())
+            format!("{}/{}", collection, record_id.as_ref())
         }
     }
 
@@ -235,6 +1042,10 @@ impl PocketBase {
 
     async fn submit_subscriptions(&self) -> Result<()> {
         let id = self.get_sse_id().await?;
+        if id.is_empty() {
+            // reconnecting, the new connection submits them
+            return Ok(());
+        }
         let keys = {
             let sub_locked = self.subscription.lock().await;
             sub_locked.keys().cloned().collect::<Vec<String>>()
@@ -243,18 +1054,49 @@ impl PocketBase {
         params.insert("clientId".to_string(), json!(id));
         params.insert("subscriptions".to_string(), json!(keys));
         let response = self.send_post(API_REALTIME, &params).await?;
+        decode_response(response).await
+    }
+}
 
-        match response.text().await {
-            Ok(body) => {
-                if body.is_empty() {
-                    Ok(())
-                } else {
-                    Err(Error::PocketBaseErrorResponse(
-                        serde_json::from_str(&body).unwrap(),
-                    ))
-                }
-            }
-            Err(e) => Err(Error::RequestFailed(Box::new(e))),
-        }
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{topic_matches, Resynced, SubscribeOptions};
+
+    #[test]
+    fn test_topic_matches() {
+        assert!(topic_matches("posts", "posts"));
+        assert!(topic_matches("posts", "posts/*"));
+        assert!(topic_matches("posts/*", "posts"));
+        assert!(topic_matches("posts/abc", "posts/abc"));
+        assert!(!topic_matches("posts", "posts/abc"));
+        assert!(!topic_matches("posts/*", "posts/abc"));
+        assert!(!topic_matches("posts", "users/abc"));
+        assert!(!topic_matches("posts/abc", "posts/xyz"));
+    }
+
+    #[test]
+    fn test_subscribe_options_topic() {
+        let options = SubscribeOptions::new().filter("a = 1").expand("author");
+        let topic = options.apply("posts/*".to_string());
+        assert_eq!(
+            topic,
+            "posts/*?options=%7B%22query%22%3A%7B%22expand%22%3A%22author%22%2C%22filter%22%3A%22a+%3D+1%22%7D%7D"
+        );
+        assert!(topic_matches(&topic, &topic));
+        assert!(topic_matches(&topic, &topic.replacen("/*", "", 1)));
+        assert!(!topic_matches("posts/*", &topic));
+        assert_eq!(SubscribeOptions::new().apply("posts".to_string()), "posts");
+    }
+
+    #[test]
+    fn test_resynced_filter() {
+        let gap_started = Utc.with_ymd_and_hms(2024, 10, 19, 8, 30, 5).unwrap();
+        let resynced = Resynced {
+            gap_started,
+            gap_ended: gap_started,
+        };
+        assert_eq!(resynced.filter(), "updated >= \"2024-10-19 08:30:05.000Z\"");
     }
 }
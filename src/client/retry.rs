@@ -0,0 +1,189 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use eventsource_client::{BackoffRetry, RetryStrategy};
+use log::debug;
+use reqwest::{header::RETRY_AFTER, Client, Method, Request, Response, StatusCode};
+
+use super::{base::SendPipeline, PocketBase};
+
+/// Retries for transient failures: connection errors, timeouts and the
+/// statuses in `retry_statuses`.
+///
+/// A server's `Retry-After` is honoured up to `delay_max`; when it asks for
+/// longer, the response is handed back instead of waiting.
+///
+/// Only idempotent requests (`GET`, `HEAD`, `OPTIONS`, `DELETE`) are retried
+/// unless `retry_non_idempotent` is set. Requests with a streamed body can't
+/// be replayed and are never retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries on top of the first attempt.
+    pub max_retries: u32,
+    pub delay: Duration,
+    pub delay_max: Duration,
+    pub backoff_factor: u32,
+    pub jitter: bool,
+    pub retry_non_idempotent: bool,
+    pub retry_statuses: Vec<StatusCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            delay: Duration::from_millis(500),
+            delay_max: Duration::from_secs(30),
+            backoff_factor: 2,
+            jitter: true,
+            retry_non_idempotent: false,
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn allows(&self, method: &Method) -> bool {
+        self.retry_non_idempotent
+            || matches!(
+                *method,
+                Method::GET | Method::HEAD | Method::OPTIONS | Method::DELETE
+            )
+    }
+
+    /// `None` when the outcome should be handed back as is, otherwise how long
+    /// to wait before the next attempt.
+    fn delay_for(
+        &self,
+        result: &reqwest::Result<Response>,
+        backoff: &mut BackoffRetry,
+    ) -> Option<Duration> {
+        match result {
+            Ok(response) if self.retry_statuses.contains(&response.status()) => {
+                match retry_after(response) {
+                    Some(delay) if delay > self.delay_max => None,
+                    Some(delay) => Some(delay),
+                    None => Some(backoff.next_delay(Instant::now())),
+                }
+            }
+            Ok(_) => None,
+            Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => {
+                Some(backoff.next_delay(Instant::now()))
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+/// `Retry-After` as either delta seconds or an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    parse_retry_after(
+        response.headers().get(RETRY_AFTER)?.to_str().ok()?,
+        Utc::now(),
+    )
+}
+
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+impl PocketBase {
+    /// Retry transient failures of outgoing requests, `None` to disable.
+    pub fn set_retry_policy(&mut self, policy: Option<RetryPolicy>) {
+        self.pipeline.lock().unwrap().retry_policy = policy;
+    }
+
+    pub fn retry_policy(&self) -> Option<RetryPolicy> {
+        self.pipeline.lock().unwrap().retry_policy.clone()
+    }
+}
+
+impl SendPipeline {
+    pub(crate) async fn execute_with_retry(
+        &self,
+        client: &Client,
+        request: Request,
+    ) -> reqwest::Result<Response> {
+        let Some(policy) = self
+            .retry_policy
+            .as_ref()
+            .filter(|p| p.allows(request.method()))
+        else {
+            self.pace(&request).await;
+            return client.execute(request).await;
+        };
+        let mut backoff = BackoffRetry::new(
+            policy.delay,
+            policy.delay_max,
+            policy.backoff_factor,
+            policy.jitter,
+        );
+
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let replay = if attempt < policy.max_retries {
+                request.try_clone()
+            } else {
+                None
+            };
+            self.pace(&request).await;
+            let result = client.execute(request).await;
+            let Some(next) = replay else {
+                return result;
+            };
+            let Some(delay) = policy.delay_for(&result, &mut backoff) else {
+                return result;
+            };
+
+            attempt += 1;
+            debug!(
+                "Retrying {} {} in {delay:?} (attempt {attempt})",
+                next.method(),
+                next.url()
+            );
+            tokio::time::sleep(delay).await;
+            request = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::parse_retry_after;
+
+    #[test]
+    fn test_retry_after_seconds() {
+        assert_eq!(
+            parse_retry_after("120", Utc::now()),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn test_retry_after_http_date() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 30).unwrap();
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
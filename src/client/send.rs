@@ -0,0 +1,103 @@
+use reqwest::{
+    header::HeaderMap,
+    multipart::{Form, Part},
+    Method, Response,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::PocketBase;
+use crate::error::{Error, PocketBaseErrorResponse, Result};
+
+/// Per-request options for [`PocketBase::send`].
+pub struct SendOptions<B = ()> {
+    pub query: Vec<(String, String)>,
+    pub headers: HeaderMap,
+    pub body: Option<B>,
+    /// `(field, part)` pairs. When set the request goes out as multipart and
+    /// `body` travels in the `@jsonPayload` field.
+    pub files: Vec<(String, Part)>,
+    /// Cancel any in-flight request with the same key, see
+    /// [`PocketBase::with_request_key`].
+    pub request_key: Option<String>,
+}
+
+impl<B> Default for SendOptions<B> {
+    fn default() -> Self {
+        SendOptions {
+            query: Vec::new(),
+            headers: HeaderMap::new(),
+            body: None,
+            files: Vec::new(),
+            request_key: None,
+        }
+    }
+}
+
+impl PocketBase {
+    /// Call any endpoint, including custom routes, with the client's auth
+    /// and PocketBase error decoding.
+    ///
+    /// An empty response body (e.g. `204` or `HEAD`) decodes as JSON `null`,
+    /// so use `()` or an `Option` for `Res` there.
+    pub async fn send<S, Req, Res>(
+        &self,
+        method: Method,
+        path: S,
+        options: SendOptions<Req>,
+    ) -> Result<Res>
+    where
+        S: AsRef<str>,
+        Req: Serialize,
+        Res: DeserializeOwned,
+    {
+        let mut req = self
+            .request(method, path)?
+            .query(&options.query)
+            .headers(options.headers);
+
+        if options.files.is_empty() {
+            if let Some(body) = &options.body {
+                req = req.json(body);
+            }
+        } else {
+            let mut form = Form::new();
+            if let Some(body) = &options.body {
+                let payload = serde_json::to_string(body)
+                    .map_err(|e| Error::InvalidParameter(Box::new(e)))?;
+                form = form.text("@jsonPayload", payload);
+            }
+            for (field, part) in options.files {
+                form = form.part(field, part);
+            }
+            req = req.multipart(form);
+        }
+
+        let request = async { decode_response(self.execute(req).await?).await };
+        match options.request_key {
+            Some(key) => self.with_request_key(key, request).await,
+            None => request.await,
+        }
+    }
+}
+
+/// Decode by status code: PocketBase error body on failure, `T` otherwise.
+pub(crate) async fn decode_response<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let success = response.status().is_success();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| Error::RequestFailed(Box::new(e)))?;
+    if !success {
+        return match serde_json::from_str::<PocketBaseErrorResponse>(&body) {
+            Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        };
+    }
+
+    let body = if body.trim().is_empty() {
+        "null"
+    } else {
+        &body
+    };
+    serde_json::from_str(body).map_err(|e| Error::RequestFailed(Box::new(e)))
+}
@@ -0,0 +1,79 @@
+use reqwest::StatusCode;
+
+use super::PocketBase;
+use crate::error::{Error, Result};
+
+/// Which PocketBase API layout the server speaks.
+///
+/// PocketBase 0.23 moved admins into the `_superusers` auth collection, which
+/// changed the admin auth and management endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerGeneration {
+    /// PocketBase <= 0.22, admins live under `/api/admins`.
+    Legacy,
+    /// PocketBase >= 0.23, admins are records of the `_superusers` collection.
+    Superusers,
+}
+
+impl ServerGeneration {
+    pub fn admin_auth_path(&self) -> &'static str {
+        match self {
+            ServerGeneration::Legacy => "/api/admins/auth-with-password",
+            ServerGeneration::Superusers => "/api/collections/_superusers/auth-with-password",
+        }
+    }
+
+    pub fn admin_refresh_path(&self) -> &'static str {
+        match self {
+            ServerGeneration::Legacy => "/api/admins/auth-refresh",
+            ServerGeneration::Superusers => "/api/collections/_superusers/auth-refresh",
+        }
+    }
+
+    pub fn admins_path(&self) -> &'static str {
+        match self {
+            ServerGeneration::Legacy => "/api/admins",
+            ServerGeneration::Superusers => "/api/collections/_superusers/records",
+        }
+    }
+}
+
+impl PocketBase {
+    /// The generation in use, `None` until it was set or detected.
+    pub fn server_generation(&self) -> Option<ServerGeneration> {
+        *self.server_generation.lock().unwrap()
+    }
+
+    /// Skip detection and always route to the given generation.
+    pub fn set_server_generation(&mut self, generation: ServerGeneration) {
+        *self.server_generation.lock().unwrap() = Some(generation);
+    }
+
+    /// Probe the server for its generation and remember the answer.
+    ///
+    /// `_superusers/auth-methods` is public on >= 0.23 and a missing
+    /// collection on older servers.
+    pub async fn detect_server_generation(&self) -> Result<ServerGeneration> {
+        let response = self
+            .send_get("/api/collections/_superusers/auth-methods", None::<&()>)
+            .await?;
+        let generation = match response.status() {
+            status if status.is_success() => ServerGeneration::Superusers,
+            StatusCode::NOT_FOUND => ServerGeneration::Legacy,
+            status => {
+                return Err(Error::PocketBaseImplementException(format!(
+                    "Unexpected status {status} while detecting server generation"
+                )))
+            }
+        };
+        *self.server_generation.lock().unwrap() = Some(generation);
+        Ok(generation)
+    }
+
+    pub(crate) async fn resolve_server_generation(&self) -> Result<ServerGeneration> {
+        match self.server_generation() {
+            Some(generation) => Ok(generation),
+            None => self.detect_server_generation().await,
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll},
+};
+
+use futures::{future, task::AtomicWaker, Future, Stream};
+use serde::de::DeserializeOwned;
+
+use super::{
+    subscription::ResyncSlot, PocketBase, RealtimeMessage, RecordEvent, Resynced, Subscription,
+    SubscriptionCallback,
+};
+use crate::error::Result;
+
+/// What a [`RecordStream`] does with an event that arrives while its buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Discard the oldest buffered event to make room.
+    #[default]
+    DropOldest,
+    /// Discard the incoming event.
+    DropNewest,
+    /// End the stream once the buffered events are consumed.
+    Close,
+}
+
+struct Queue<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    closed: bool,
+}
+
+impl<T> Queue<T> {
+    fn new(capacity: usize, overflow: OverflowPolicy) -> Self {
+        Queue {
+            items: VecDeque::new(),
+            capacity: capacity.max(1),
+            overflow,
+            closed: false,
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        if self.closed {
+            return;
+        }
+        if self.items.len() >= self.capacity {
+            match self.overflow {
+                OverflowPolicy::DropOldest => {
+                    self.items.pop_front();
+                }
+                OverflowPolicy::DropNewest => return,
+                OverflowPolicy::Close => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+        self.items.push_back(item);
+    }
+}
+
+struct Shared<T> {
+    queue: StdMutex<Queue<T>>,
+    waker: AtomicWaker,
+}
+
+impl<T> Shared<T> {
+    fn new(capacity: usize, overflow: OverflowPolicy) -> Self {
+        Shared {
+            queue: StdMutex::new(Queue::new(capacity, overflow)),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    fn push(&self, item: T) {
+        self.queue.lock().unwrap().push(item);
+        self.waker.wake();
+    }
+
+    /// End the stream once the buffered events are consumed.
+    fn close(&self) {
+        self.queue.lock().unwrap().closed = true;
+        self.waker.wake();
+    }
+}
+
+/// Feed `listener` the realtime messages, and end the stream on
+/// [`RealtimeMessage::Closed`].
+fn closing_listener<T: Send + 'static>(
+    shared: Arc<Shared<T>>,
+    listener: SubscriptionCallback,
+) -> SubscriptionCallback {
+    Arc::new(move |message| match message {
+        RealtimeMessage::Closed => {
+            shared.close();
+            Box::pin(future::ready(()))
+        }
+        message => listener(message),
+    })
+}
+
+/// Record events of one topic as a [`Stream`]. Dropping it removes the
+/// subscription like dropping a [`Subscription`]. The stream ends when the
+/// topic is unsubscribed or the realtime connection closes for good.
+pub struct RecordStream<T> {
+    shared: Arc<Shared<RecordEvent<T>>>,
+    subscription: Subscription,
+}
+
+impl<T> RecordStream<T> {
+    pub fn topic(&self) -> &str {
+        self.subscription.topic()
+    }
+
+    /// Told about gaps in the stream, see [`Subscription::on_resync`].
+    pub fn on_resync<F, Fut>(&self, callback: F)
+    where
+        F: Fn(Resynced) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.subscription.on_resync(callback)
+    }
+
+    /// Remove the subscription, see [`Subscription::unsubscribe`].
+    pub async fn unsubscribe(self) {
+        self.subscription.unsubscribe().await
+    }
+}
+
+impl<T> Stream for RecordStream<T> {
+    type Item = RecordEvent<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.shared.waker.register(cx.waker());
+        let mut queue = self.shared.queue.lock().unwrap();
+        match queue.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if queue.closed => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl PocketBase {
+    /// Like [`subscribe`](Self::subscribe), but hands the events out as a
+    /// stream buffering at most `buffer` of them.
+    pub async fn subscribe_stream<T, S>(
+        &mut self,
+        collection: S,
+        record_id: S,
+        buffer: usize,
+        overflow: OverflowPolicy,
+    ) -> Result<RecordStream<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+        S: AsRef<str>,
+    {
+        let generation = self.resolve_server_generation().await?;
+        let topic = Self::resolve_subscribe_to(collection, record_id, generation);
+        let shared = Arc::new(Shared::new(buffer, overflow));
+
+        let sink = shared.clone();
+        let resync = ResyncSlot::default();
+        let listener = self.typed_listener(
+            topic.clone(),
+            move |event: RecordEvent<T>| {
+                sink.push(event);
+                future::ready(())
+            },
+            resync.clone(),
+        );
+        let listener = closing_listener(shared.clone(), listener);
+        let subscription = self.add_listener(topic, listener, resync).await?;
+
+        Ok(RecordStream {
+            shared,
+            subscription,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use futures::{future, StreamExt};
+
+    use super::{closing_listener, OverflowPolicy, Queue, RecordStream, Shared};
+    use crate::client::{
+        subscription::ResyncSlot, PocketBase, RecordAction, RecordEvent, Subscription,
+    };
+
+    fn filled(overflow: OverflowPolicy) -> Queue<u32> {
+        let mut queue = Queue::new(2, overflow);
+        for i in 1..=3 {
+            queue.push(i);
+        }
+        queue
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let queue = filled(OverflowPolicy::DropOldest);
+        assert_eq!(queue.items, [2, 3]);
+        assert!(!queue.closed);
+
+        let queue = filled(OverflowPolicy::DropNewest);
+        assert_eq!(queue.items, [1, 2]);
+        assert!(!queue.closed);
+
+        let mut queue = filled(OverflowPolicy::Close);
+        assert_eq!(queue.items, [1, 2]);
+        assert!(queue.closed);
+        queue.items.clear();
+        queue.push(4);
+        assert!(queue.items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_when_unsubscribed() {
+        let mut pb = PocketBase::new("http://127.0.0.1:8090").unwrap();
+        let shared = Arc::new(Shared::new(4, OverflowPolicy::DropOldest));
+        let listener = closing_listener(shared.clone(), Arc::new(|_| Box::pin(future::ready(()))));
+        pb.subscription
+            .lock()
+            .await
+            .insert("posts/*".to_string(), vec![listener.clone()]);
+        let event = RecordEvent {
+            action: RecordAction::Create,
+            record: 1,
+        };
+        shared.push(event.clone());
+        let mut stream = RecordStream {
+            shared,
+            subscription: Subscription::new(
+                "posts/*".to_string(),
+                listener,
+                pb.subscription.clone(),
+                None,
+                ResyncSlot::default(),
+            ),
+        };
+
+        // no realtime connection to resubmit to, the listener is gone regardless
+        let _ = pb.unsubscribe("", "").await;
+        assert_eq!(stream.next().await, Some(event));
+        let end = tokio::time::timeout(Duration::from_secs(1), stream.next()).await;
+        assert_eq!(end, Ok(None));
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex as StdMutex};
+
+use futures::Future;
+use tokio::sync::{mpsc::UnboundedSender, oneshot, Mutex};
+
+use super::{
+    realtime::RealtimeCommand, HashMapSubscription, ResyncCallback, Resynced, SubscriptionCallback,
+};
+
+/// Resync callback of one subscription, shared with its listener.
+pub(crate) type ResyncSlot = Arc<StdMutex<Option<ResyncCallback>>>;
+
+struct Registration {
+    topic: String,
+    listener: SubscriptionCallback,
+    subscription: Arc<Mutex<HashMapSubscription>>,
+    commands: Option<UnboundedSender<RealtimeCommand>>,
+}
+
+impl Registration {
+    /// Remove the listener, returning whether its topic went away with it.
+    fn remove_from(&self, sub: &mut HashMapSubscription) -> bool {
+        let Some(listeners) = sub.get_mut(&self.topic) else {
+            return false;
+        };
+        listeners.retain(|l| !Arc::ptr_eq(l, &self.listener));
+        if !listeners.is_empty() {
+            return false;
+        }
+        sub.remove(&self.topic);
+        true
+    }
+
+    fn resubmit(&self, ack: Option<oneshot::Sender<()>>) {
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(RealtimeCommand::Resubmit(ack));
+        }
+    }
+}
+
+/// One realtime listener. Dropping it, or calling
+/// [`unsubscribe`](Self::unsubscribe), removes just that listener; the topic is
+/// unsubscribed on the server once its last listener is gone.
+#[must_use = "the listener is removed when the subscription is dropped"]
+pub struct Subscription {
+    registration: Option<Registration>,
+    resync: ResyncSlot,
+}
+
+impl Subscription {
+    pub(crate) fn new(
+        topic: String,
+        listener: SubscriptionCallback,
+        subscription: Arc<Mutex<HashMapSubscription>>,
+        commands: Option<UnboundedSender<RealtimeCommand>>,
+        resync: ResyncSlot,
+    ) -> Self {
+        Subscription {
+            registration: Some(Registration {
+                topic,
+                listener,
+                subscription,
+                commands,
+            }),
+            resync,
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        self.registration
+            .as_ref()
+            .map(|r| r.topic.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Called when the realtime connection came back from an outage, with the
+    /// window in which events of this subscription were missed. Replaces the
+    /// previous callback.
+    pub fn on_resync<F, Fut>(&self, callback: F)
+    where
+        F: Fn(Resynced) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        *self.resync.lock().unwrap() = Some(Arc::new(move |resynced| Box::pin(callback(resynced))));
+    }
+
+    /// Remove the listener, waiting until the server got the new topic list.
+    pub async fn unsubscribe(mut self) {
+        let Some(registration) = self.registration.take() else {
+            return;
+        };
+        let topic_gone = registration.remove_from(&mut *registration.subscription.lock().await);
+        if topic_gone {
+            let (ack, done) = oneshot::channel();
+            registration.resubmit(Some(ack));
+            let _ = done.await;
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let Some(registration) = self.registration.take() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    let subscription = registration.subscription.clone();
+                    if registration.remove_from(&mut *subscription.lock().await) {
+                        registration.resubmit(None);
+                    }
+                });
+            }
+            Err(_) => {
+                if let Ok(mut sub) = registration.subscription.try_lock() {
+                    if registration.remove_from(&mut sub) {
+                        registration.resubmit(None);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    client::PocketBase,
+    error::{Error, PocketBaseErrorResponse, Result},
+    records::operations::GeneralPocketBaseResponse,
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CronJob {
+    pub id: String,
+    /// Cron schedule, e.g. `0 */6 * * *`.
+    pub expression: String,
+}
+
+// Only available on PocketBase >= 0.23.
+static API_CRONS: &str = "/api/crons";
+
+impl PocketBase {
+    pub async fn list_crons(&self) -> Result<Vec<CronJob>> {
+        match self
+            .send_get(API_CRONS, None::<&()>)
+            .await?
+            .json::<GeneralPocketBaseResponse<Vec<CronJob>>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    /// Trigger a registered cron job right away, outside of its schedule.
+    pub async fn run_cron<S: AsRef<str>>(&self, id: S) -> Result<()> {
+        let response = self
+            .send_post(format!("{API_CRONS}/{}", id.as_ref()), &json!({}))
+            .await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        match response.json::<PocketBaseErrorResponse>().await {
+            Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+}
@@ -5,9 +5,11 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PocketBaseErrorResponse {
-    code: u8,
-    message: String,
-    data: HashMap<String, String>,
+    #[serde(alias = "status")]
+    pub code: u16,
+    pub message: String,
+    #[serde(default)]
+    pub data: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug)]
@@ -20,6 +22,12 @@ pub enum Error {
     PocketBaseErrorResponse(PocketBaseErrorResponse),
     PocketBaseImplementException(String),
     Timeout(String),
+    /// The record changed on the server since the caller last saw it.
+    /// Carries the current server copy so the caller can merge.
+    Conflict(serde_json::Value),
+    /// Superseded by a newer request with the same request key, or cancelled
+    /// explicitly.
+    Cancelled,
     SSEClientNotExist,
     ShouldNot(String),
 }
@@ -37,6 +45,12 @@ impl std::fmt::Display for Error {
                 write!(f, "PocketBase request error: {response_err:#?}")
             }
             Timeout(reason) => write!(f, "Timeout: {reason}"),
+            Conflict(current) => write!(
+                f,
+                "Conflict: record was modified on the server (updated={})",
+                current.get("updated").unwrap_or(&serde_json::Value::Null)
+            ),
+            Cancelled => write!(f, "Request cancelled"),
             SSEClientNotExist => write!(f, "SSE Client not created yet"),
             NotAuthenticated => write!(f, "Not authenticated"),
             ShouldNot(reason) => write!(f, "Should not: {reason}"),
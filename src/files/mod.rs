@@ -0,0 +1,30 @@
+use serde::Deserialize;
+
+use crate::{
+    client::PocketBase,
+    error::{Error, Result},
+    records::operations::GeneralPocketBaseResponse,
+};
+
+#[derive(Deserialize, Debug)]
+struct FileTokenResponse {
+    token: String,
+}
+
+impl PocketBase {
+    /// Short-lived token for protected files and backup downloads.
+    pub async fn file_token(&self) -> Result<String> {
+        match self
+            .send_post("/api/files/token", &serde_json::json!({}))
+            .await?
+            .json::<GeneralPocketBaseResponse<FileTokenResponse>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res.token),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+}
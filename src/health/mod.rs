@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::PocketBase,
+    error::{Error, PocketBaseErrorResponse, Result},
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    pub code: u16,
+    pub message: String,
+    #[serde(default)]
+    pub data: HealthData,
+}
+
+/// Only filled in for admin/superuser requests.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthData {
+    #[serde(default)]
+    pub can_backup: bool,
+    #[serde(rename = "realIP", skip_serializing_if = "Option::is_none")]
+    pub real_ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub possible_proxy_header: Option<String>,
+}
+
+impl PocketBase {
+    pub async fn health(&self) -> Result<HealthResponse> {
+        let response = self.send_get("/api/health", None::<&()>).await?;
+        if !response.status().is_success() {
+            return match response.json::<PocketBaseErrorResponse>().await {
+                Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+                Err(e) => Err(Error::RequestFailed(Box::new(e))),
+            };
+        }
+
+        response
+            .json::<HealthResponse>()
+            .await
+            .map_err(|e| Error::RequestFailed(Box::new(e)))
+    }
+
+    /// Poll [`health`](Self::health) every `interval` until it succeeds,
+    /// giving up with [`Error::Timeout`] after `timeout`.
+    pub async fn wait_until_healthy(
+        &self,
+        timeout: Duration,
+        interval: Duration,
+    ) -> Result<HealthResponse> {
+        let now = Instant::now();
+        loop {
+            let remaining = timeout.saturating_sub(now.elapsed());
+            match tokio::time::timeout(remaining, self.health()).await {
+                Ok(Ok(health)) => return Ok(health),
+                Ok(Err(e)) => debug!("Not healthy yet: {e}"),
+                Err(_) => debug!("Health check timed out"),
+            }
+
+            let elapsed = now.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout("Overtime wait server healthy".to_string()));
+            }
+            tokio::time::sleep(interval.min(timeout - elapsed)).await;
+        }
+    }
+}
@@ -2,3 +2,10 @@ pub mod client;
 pub mod user;
 pub mod error;
 pub mod records;
+pub mod admins;
+pub mod settings;
+pub mod logs;
+pub mod backups;
+pub mod files;
+pub mod crons;
+pub mod health;
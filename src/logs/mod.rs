@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    client::PocketBase,
+    error::{Error, Result},
+    records::operations::{
+        list::{ListQuery, PaginatedRecordList},
+        GeneralPocketBaseResponse,
+    },
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub id: String,
+    pub created: String,
+    #[serde(default)]
+    pub updated: String,
+    /// slog levels: -4 debug, 0 info, 4 warn, 8 error.
+    pub level: i32,
+    pub message: String,
+    pub data: LogData,
+}
+
+/// `data` of a request log. Non-request logs carry their own keys, which end
+/// up in `extra`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogData {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec_time: Option<f64>,
+    /// `guest`, the auth collection name or `_superusers` (`admin` on <= 0.22).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_id: Option<String>,
+    #[serde(rename = "userIP", skip_serializing_if = "Option::is_none")]
+    pub user_ip: Option<String>,
+    #[serde(rename = "remoteIP", skip_serializing_if = "Option::is_none")]
+    pub remote_ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Number of logs in one hourly bucket.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogStat {
+    pub total: u64,
+    /// Start of the hour, e.g. `2024-01-01 10:00:00.000Z`.
+    pub date: String,
+}
+
+static API_LOGS: &str = "/api/logs";
+
+impl PocketBase {
+    pub async fn list_logs(&self, query: ListQuery) -> Result<PaginatedRecordList<LogEntry>> {
+        match self
+            .send_get(API_LOGS, Some(&query.to_params()))
+            .await?
+            .json::<GeneralPocketBaseResponse<PaginatedRecordList<LogEntry>>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    pub async fn view_log<S: AsRef<str>>(&self, id: S) -> Result<LogEntry> {
+        match self
+            .send_get(format!("{API_LOGS}/{}", id.as_ref()), None::<&()>)
+            .await?
+            .json::<GeneralPocketBaseResponse<LogEntry>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    /// Hourly log counts, e.g. `log_stats(Some("data.status >= 500"))` for 5xx rates.
+    pub async fn log_stats<S: AsRef<str>>(&self, filter: Option<S>) -> Result<Vec<LogStat>> {
+        let mut params = HashMap::new();
+        if let Some(inner) = filter {
+            params.insert("filter", inner.as_ref().to_string());
+        }
+        match self
+            .send_get(format!("{API_LOGS}/stats"), Some(&params))
+            .await?
+            .json::<GeneralPocketBaseResponse<Vec<LogStat>>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+}
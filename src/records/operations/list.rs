@@ -19,6 +19,72 @@ pub struct PaginatedRecordList<T> {
     pub items: Vec<T>,
 }
 
+/// Paging, sorting and filtering shared by the list endpoints.
+///
+/// ```
+/// # use pocketbase_sdk_rust::records::operations::list::ListQuery;
+/// let query = ListQuery::new().page(2).per_page(50).filter("data.status >= 500");
+/// ```
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ListQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub sort: Option<String>,
+    pub filter: Option<String>,
+    pub expand: Option<String>,
+}
+
+impl ListQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page);
+        self
+    }
+
+    pub fn sort<S: Into<String>>(mut self, sort: S) -> Self {
+        self.sort = Some(sort.into());
+        self
+    }
+
+    pub fn filter<S: Into<String>>(mut self, filter: S) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn expand<S: Into<String>>(mut self, expand: S) -> Self {
+        self.expand = Some(expand.into());
+        self
+    }
+
+    pub(crate) fn to_params(&self) -> HashMap<&'static str, String> {
+        let mut params = HashMap::new();
+        if let Some(inner) = self.page {
+            params.insert("page", inner.to_string());
+        }
+        if let Some(inner) = self.per_page {
+            params.insert("perPage", inner.to_string());
+        }
+        if let Some(inner) = &self.sort {
+            params.insert("sort", inner.to_string());
+        }
+        if let Some(inner) = &self.filter {
+            params.insert("filter", inner.to_string());
+        }
+        if let Some(inner) = &self.expand {
+            params.insert("expand", inner.to_string());
+        }
+        params
+    }
+}
+
 impl PocketBase {
     pub async fn get_list<S: AsRef<str>, T: DeserializeOwned>(
         &self,
@@ -29,26 +95,17 @@ impl PocketBase {
         filter: Option<S>,
         expand: Option<S>,
     ) -> Result<PaginatedRecordList<T>> {
-        let mut params = HashMap::new();
-        if let Some(inner) = page {
-            params.insert("page", inner.to_string());
-        }
-        if let Some(inner) = per_page {
-            params.insert("perPage", inner.to_string());
-        }
-        if let Some(inner) = sort {
-            params.insert("sort", inner.as_ref().to_string());
-        }
-        if let Some(inner) = filter {
-            params.insert("filter", inner.as_ref().to_string());
-        }
-        if let Some(inner) = expand {
-            params.insert("expand", inner.as_ref().to_string());
-        }
+        let query = ListQuery {
+            page,
+            per_page,
+            sort: sort.map(|inner| inner.as_ref().to_string()),
+            filter: filter.map(|inner| inner.as_ref().to_string()),
+            expand: expand.map(|inner| inner.as_ref().to_string()),
+        };
         match self
             .send_get(
                 format!("/api/collections/{}/records", collection.as_ref()),
-                Some(&params),
+                Some(&query.to_params()),
             )
             .await?
             .json::<GeneralPocketBaseResponse<PaginatedRecordList<T>>>()
@@ -1,16 +1,34 @@
+use reqwest::Response;
 use serde::Deserialize;
-use crate::error::PocketBaseErrorResponse;
+use serde_json::Value;
+use crate::error::{Error, PocketBaseErrorResponse, Result};
 
 pub mod view;
 pub mod create;
 mod delete;
 mod update;
-mod list;
+pub mod list;
+pub mod upsert;
 
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase", untagged)]
-enum GeneralPocketBaseResponse<T> {
+pub(crate) enum GeneralPocketBaseResponse<T> {
     SuccessResponse(T),
     ErrorResponse(PocketBaseErrorResponse),
 }
+
+// The untagged `GeneralPocketBaseResponse` can't tell an error body from a
+// `Value` record, so go by the status code here.
+async fn parse_record(response: Response) -> Result<Value> {
+    if response.status().is_success() {
+        return response
+            .json::<Value>()
+            .await
+            .map_err(|e| Error::RequestFailed(Box::new(e)));
+    }
+    match response.json::<PocketBaseErrorResponse>().await {
+        Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+        Err(e) => Err(Error::RequestFailed(Box::new(e))),
+    }
+}
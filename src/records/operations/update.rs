@@ -1,8 +1,9 @@
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
 use crate::{client::PocketBase, error::{Result, Error}};
 
-use super::GeneralPocketBaseResponse;
+use super::{parse_record, GeneralPocketBaseResponse};
 
 impl PocketBase {
     pub async fn update<S: AsRef<str>, T: Serialize + DeserializeOwned>(
@@ -31,4 +32,39 @@ impl PocketBase {
             Err(e) => Err(Error::RequestFailed(Box::new(e))),
         }
     }
+
+    /// Like [`update`](Self::update) but only applies when the record's
+    /// `updated` timestamp still equals `expected_updated`, otherwise returns
+    /// [`Error::Conflict`] with the current server copy. Collections without
+    /// an `updated` autodate field can't be compared and fail with
+    /// [`Error::PocketBaseImplementException`].
+    ///
+    /// PocketBase has no conditional update, so this is a view-compare-update:
+    /// a write landing between the view and the patch can still slip through.
+    pub async fn update_if_unmodified<S: AsRef<str>, T: Serialize + DeserializeOwned>(
+        &self,
+        collection: S,
+        id: S,
+        expected_updated: S,
+        model: &T,
+    ) -> Result<T> {
+        let path = format!(
+            "/api/collections/{}/records/{}",
+            collection.as_ref(),
+            id.as_ref()
+        );
+        let current = parse_record(self.send_get(&path, None::<&()>).await?).await?;
+        let Some(server_updated) = current.get("updated").and_then(Value::as_str) else {
+            return Err(Error::PocketBaseImplementException(format!(
+                "collection {} has no updated field to compare",
+                collection.as_ref()
+            )));
+        };
+        if server_updated != expected_updated.as_ref() {
+            return Err(Error::Conflict(current));
+        }
+
+        let updated = parse_record(self.send_patch(&path, model).await?).await?;
+        serde_json::from_value(updated).map_err(|e| Error::RequestFailed(Box::new(e)))
+    }
 }
@@ -0,0 +1,176 @@
+use reqwest::StatusCode;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    client::PocketBase,
+    error::{Error, Result},
+};
+
+use super::{parse_record, GeneralPocketBaseResponse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertAction {
+    /// Inserted. After a batch upsert this is inferred from `created` and
+    /// `updated` being equal, which is a guess: since 0.23 each autodate
+    /// field takes its own timestamp, so an insert straddling a millisecond
+    /// is reported as `Updated`.
+    Created,
+    /// Updated an existing record. After a batch upsert this may also be an
+    /// insert, see `Created`.
+    Updated,
+    /// The batch upsert succeeded but the returned record has no
+    /// `created`/`updated` fields to tell an insert from an update. Since
+    /// 0.23 these autodate fields are optional, so expect this there unless
+    /// the collection keeps them; use [`UpsertMode::ViewThenWrite`] when the
+    /// distinction matters.
+    Upserted,
+}
+
+/// How [`PocketBase::upsert_with`] writes the record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpsertMode {
+    /// A single batch `upsert` op, falling back to `ViewThenWrite` when batch
+    /// requests are missing or disabled. The action is a best guess and may
+    /// be `Upserted`; don't count inserts and updates from it.
+    #[default]
+    Batch,
+    /// `view`, then `create` or `update`. Two requests and not atomic, but the
+    /// action is always exactly `Created` or `Updated`.
+    ViewThenWrite,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpsertResult<R> {
+    pub record: R,
+    pub action: UpsertAction,
+    /// `true` when the server handled it with a batch `upsert` op,
+    /// `false` when we fell back to `view` + `update`/`create`.
+    pub via_batch: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct BatchResponse {
+    status: u16,
+    body: Value,
+}
+
+impl PocketBase {
+    /// Create or update a record by the `id` carried in `model`.
+    ///
+    /// Uses the batch `upsert` op (PocketBase >= 0.23) and falls back to
+    /// `view` + `update`/`create` when batch requests are missing or disabled.
+    /// See [`UpsertAction::Upserted`] for when the action can't be told.
+    pub async fn upsert<S: AsRef<str>, T: Serialize, R: DeserializeOwned>(
+        &self,
+        collection: S,
+        model: &T,
+    ) -> Result<UpsertResult<R>> {
+        self.upsert_with(collection, model, UpsertMode::Batch).await
+    }
+
+    /// [`upsert`](Self::upsert) with a choice of how the record is written.
+    pub async fn upsert_with<S: AsRef<str>, T: Serialize, R: DeserializeOwned>(
+        &self,
+        collection: S,
+        model: &T,
+        mode: UpsertMode,
+    ) -> Result<UpsertResult<R>> {
+        let model =
+            serde_json::to_value(model).map_err(|e| Error::InvalidParameter(Box::new(e)))?;
+        let id = match model.get("id").and_then(Value::as_str) {
+            Some(id) if !id.is_empty() => id.to_string(),
+            _ => {
+                return Err(Error::InvalidParameter(
+                    "upsert requires the model to carry a non-empty `id`".into(),
+                ))
+            }
+        };
+
+        if mode == UpsertMode::Batch {
+            if let Some(result) = self.upsert_via_batch(collection.as_ref(), &model).await? {
+                return Ok(result);
+            }
+        }
+
+        let records_path = format!("/api/collections/{}/records", collection.as_ref());
+        let record_path = format!("{records_path}/{id}");
+        let existing = self.send_get(&record_path, None::<&()>).await?;
+        let (response, action) = match existing.status() {
+            StatusCode::NOT_FOUND => (
+                self.send_post(&records_path, &model).await?,
+                UpsertAction::Created,
+            ),
+            _ => {
+                parse_record(existing).await?;
+                (
+                    self.send_patch(&record_path, &model).await?,
+                    UpsertAction::Updated,
+                )
+            }
+        };
+        let record = parse_record(response).await?;
+
+        Ok(UpsertResult {
+            record: serde_json::from_value(record)
+                .map_err(|e| Error::RequestFailed(Box::new(e)))?,
+            action,
+            via_batch: false,
+        })
+    }
+
+    /// Returns `None` when the server has no (or a disabled) batch endpoint.
+    async fn upsert_via_batch<R: DeserializeOwned>(
+        &self,
+        collection: &str,
+        model: &Value,
+    ) -> Result<Option<UpsertResult<R>>> {
+        let body = json!({
+            "requests": [{
+                "method": "PUT",
+                "url": format!("/api/collections/{collection}/records"),
+                "body": model,
+            }]
+        });
+        let response = self.send_post("/api/batch", &body).await?;
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN
+        ) {
+            return Ok(None);
+        }
+
+        let mut responses = match response
+            .json::<GeneralPocketBaseResponse<Vec<BatchResponse>>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => res,
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                return Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => return Err(Error::RequestFailed(Box::new(e))),
+        };
+        let Some(BatchResponse { status, body }) = responses.pop() else {
+            return Err(Error::PocketBaseImplementException(
+                "Batch response came back empty".to_string(),
+            ));
+        };
+        if !(200..300).contains(&status) {
+            return match serde_json::from_value(body) {
+                Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+                Err(e) => Err(Error::RequestFailed(Box::new(e))),
+            };
+        }
+
+        let action = match (body.get("created"), body.get("updated")) {
+            (Some(created), Some(updated)) if created == updated => UpsertAction::Created,
+            (Some(_), Some(_)) => UpsertAction::Updated,
+            _ => UpsertAction::Upserted,
+        };
+        Ok(Some(UpsertResult {
+            record: serde_json::from_value(body).map_err(|e| Error::RequestFailed(Box::new(e)))?,
+            action,
+            via_batch: true,
+        }))
+    }
+}
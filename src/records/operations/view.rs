@@ -23,7 +23,7 @@ impl PocketBase {
         match self
             .send_get(
                 format!(
-                    "/api/collections/{}/record/{}",
+                    "/api/collections/{}/records/{}",
                     collection.as_ref(),
                     id.as_ref()
                 ),
@@ -0,0 +1,291 @@
+use std::time::Duration;
+
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    client::PocketBase,
+    error::{Error, PocketBaseErrorResponse, Result},
+    records::operations::GeneralPocketBaseResponse,
+};
+
+/// What the server sends back in place of a stored secret.
+pub const MASKED_SECRET: &str = "******";
+
+// Secrets come back masked (or blank), sending that back would overwrite
+// the stored value, so leave them out of updates unless set explicitly.
+fn is_unset_secret(secret: &Option<String>) -> bool {
+    match secret {
+        Some(secret) => secret.is_empty() || secret == MASKED_SECRET,
+        None => true,
+    }
+}
+
+/// App settings. Every field is optional so a value fetched with
+/// [`PocketBase::get_settings`] can be trimmed down to only the parts to
+/// change before passing it to [`PocketBase::update_settings`].
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<MetaSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smtp: Option<SmtpSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3: Option<S3Settings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backups: Option<BackupsSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limits: Option<RateLimitsSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<BatchSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_proxy: Option<TrustedProxySettings>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetaSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+    #[serde(rename = "appURL", skip_serializing_if = "Option::is_none")]
+    pub app_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hide_controls: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmtpSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "is_unset_secret")]
+    pub password: Option<String>,
+    /// `PLAIN` or `LOGIN`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_name: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_key: Option<String>,
+    #[serde(skip_serializing_if = "is_unset_secret")]
+    pub secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_path_style: Option<bool>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupsSettings {
+    /// Cron expression for automatic backups, empty to disable them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron_max_keep: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3: Option<S3Settings>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitsSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<RateLimitRule>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitRule {
+    /// Path prefix (`/api/`), `METHOD /path` or a `collection:action` tag.
+    pub label: String,
+    /// Empty for everyone, otherwise `@guest` or `@auth`.
+    #[serde(default)]
+    pub audience: String,
+    /// Window length in seconds.
+    pub duration: u64,
+    pub max_requests: u32,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_requests: Option<u32>,
+    /// Seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedProxySettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<Vec<String>>,
+    #[serde(rename = "useLeftmostIP", skip_serializing_if = "Option::is_none")]
+    pub use_leftmost_ip: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AppleClientSecretResponse {
+    secret: String,
+}
+
+static API_SETTINGS: &str = "/api/settings";
+
+impl PocketBase {
+    pub async fn get_settings(&self) -> Result<Settings> {
+        let response = self.send_get(API_SETTINGS, None::<&()>).await?;
+        Self::parse_settings_response(response).await
+    }
+
+    /// Only the fields set in `settings` are sent, masked secrets are left out.
+    pub async fn update_settings(&self, settings: &Settings) -> Result<Settings> {
+        let response = self.send_patch(API_SETTINGS, settings).await?;
+        Self::parse_settings_response(response).await
+    }
+
+    // Every field of Settings is optional, so an error body would happily
+    // parse as `Settings` through `GeneralPocketBaseResponse`.
+    async fn parse_settings_response(response: Response) -> Result<Settings> {
+        if !response.status().is_success() {
+            return match response.json::<PocketBaseErrorResponse>().await {
+                Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+                Err(e) => Err(Error::RequestFailed(Box::new(e))),
+            };
+        }
+
+        response
+            .json::<Settings>()
+            .await
+            .map_err(|e| Error::RequestFailed(Box::new(e)))
+    }
+
+    /// `filesystem` is either `storage` or `backups`.
+    pub async fn test_s3<S: AsRef<str>>(&self, filesystem: S) -> Result<()> {
+        let response = self
+            .send_post(
+                format!("{API_SETTINGS}/test/s3"),
+                &json!({ "filesystem": filesystem.as_ref() }),
+            )
+            .await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        match response.json::<PocketBaseErrorResponse>().await {
+            Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    /// `template` is one of `verification`, `password-reset`, `email-change`,
+    /// `otp` or `login-alert`.
+    pub async fn test_email<S: AsRef<str>>(&self, collection: S, to: S, template: S) -> Result<()> {
+        let response = self
+            .send_post(
+                format!("{API_SETTINGS}/test/email"),
+                &json!({
+                    "collection": collection.as_ref(),
+                    "email": to.as_ref(),
+                    "template": template.as_ref(),
+                }),
+            )
+            .await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+
+        match response.json::<PocketBaseErrorResponse>().await {
+            Ok(res) => Err(Error::PocketBaseErrorResponse(res)),
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+
+    /// Sign a client secret for the Apple OAuth2 provider, valid for `duration`
+    /// (Apple caps it at ~6 months).
+    pub async fn generate_apple_client_secret<S: AsRef<str>>(
+        &self,
+        client_id: S,
+        team_id: S,
+        key_id: S,
+        private_key: S,
+        duration: Duration,
+    ) -> Result<String> {
+        match self
+            .send_post(
+                format!("{API_SETTINGS}/apple/generate-client-secret"),
+                &json!({
+                    "clientId": client_id.as_ref(),
+                    "teamId": team_id.as_ref(),
+                    "keyId": key_id.as_ref(),
+                    "privateKey": private_key.as_ref(),
+                    "duration": duration.as_secs(),
+                }),
+            )
+            .await?
+            .json::<GeneralPocketBaseResponse<AppleClientSecretResponse>>()
+            .await
+        {
+            Ok(GeneralPocketBaseResponse::SuccessResponse(res)) => Ok(res.secret),
+            Ok(GeneralPocketBaseResponse::ErrorResponse(res)) => {
+                Err(Error::PocketBaseErrorResponse(res))
+            }
+            Err(e) => Err(Error::RequestFailed(Box::new(e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Settings, SmtpSettings, MASKED_SECRET};
+
+    #[test]
+    fn test_masked_secret_not_sent_back() {
+        let settings = Settings {
+            smtp: Some(SmtpSettings {
+                host: Some("smtp.example.com".to_string()),
+                password: Some(MASKED_SECRET.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            serde_json::to_value(&settings).unwrap(),
+            serde_json::json!({ "smtp": { "host": "smtp.example.com" } })
+        );
+    }
+}
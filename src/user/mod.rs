@@ -1,6 +1,6 @@
 use crate::error::{Error, Result};
 use base64::Engine;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use serde_json::Map;
@@ -9,6 +9,8 @@ use serde_json::Map;
 #[serde(rename_all = "camelCase")]
 pub struct AdminResponse {
     pub token: String,
+    /// `admin` on PocketBase <= 0.22, `record` for `_superusers` on newer servers.
+    #[serde(alias = "record")]
     pub admin: AdminRecord,
 }
 
@@ -19,6 +21,7 @@ pub struct AdminRecord {
     pub created: String,
     pub updated: String,
     pub email: String,
+    #[serde(default)]
     pub avatar: i64,
 }
 
@@ -122,11 +125,7 @@ impl User {
                     if let Some(expired_json) = json_value.get("exp") {
                         debug!("payload had json exp: {expired_json}");
                         if let Some(expired) = expired_json.as_i64() {
-                            if let Some(native_datetime) =
-                                NaiveDateTime::from_timestamp_millis(expired * 1000)
-                            {
-                                let expired_datetime =
-                                    DateTime::<Utc>::from_utc(native_datetime, Utc);
+                            if let Some(expired_datetime) = Utc.timestamp_opt(expired, 0).single() {
                                 debug!("expired at: {expired_datetime}");
                                 return Ok(expired_datetime);
                             }